@@ -1,7 +1,53 @@
-use std::process::Command;
+//! Creates a database with the current schema for the `query!` macros to check against.
+//! This does not touch any user's database; those are upgraded at runtime by `Connection::new`.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use sqlx::{
+    ConnectOptions,
+    Connection,
+    sqlite::SqliteConnectOptions,
+};
+
+#[path = "src/migrations.rs"]
+#[allow(dead_code)]
+mod migrations;
 
 fn main() {
-    Command::new("make")
-        .status()
-        .expect("Failed to run make");
+    println!("cargo:rerun-if-changed=resources");
+    println!("cargo:rerun-if-changed=src/migrations.rs");
+
+    let database_path = PathBuf::from(env::var("OUT_DIR").expect("Cargo always sets OUT_DIR")).join("beavor_dev.db");
+
+    // Always start from scratch so that edits to the schema or upgrade scripts are picked up
+    let _ = fs::remove_file(&database_path);
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Should be able to start a runtime")
+        .block_on(async {
+            let mut conn = SqliteConnectOptions::new()
+                .filename(&database_path)
+                .create_if_missing(true)
+                .foreign_keys(false)
+                .connect()
+                .await
+                .expect("Should be able to create the development database");
+
+            sqlx::query(include_str!("resources/schema.sql"))
+                .execute(&mut conn)
+                .await
+                .expect("Should be able to apply the schema");
+
+            migrations::migrate(&mut conn)
+                .await
+                .expect("Should be able to apply the upgrade scripts");
+
+            conn.close().await.expect("Should be able to close the development database");
+        });
+
+    println!("cargo:rustc-env=DATABASE_URL=sqlite:{}", database_path.display());
 }
@@ -0,0 +1,3 @@
+-- Upgrade from schema_v1.9 to schema_v1.10
+-- Counts the updates to each task, so that an update based on an outdated copy can be refused
+ALTER TABLE tasks ADD COLUMN Revision INTEGER NOT NULL DEFAULT 0;
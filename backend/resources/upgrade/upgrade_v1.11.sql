@@ -0,0 +1,21 @@
+-- Upgrade from schema_v1.10 to schema_v1.11
+-- Holidays come from a configurable source, so record where each came from, and which years
+-- have been fetched from which source
+ALTER TABLE days_off ADD COLUMN Source TEXT;
+
+CREATE TABLE holiday_years(
+    Year      INTEGER NOT NULL,
+    Source    TEXT    NOT NULL,
+    FetchedAt TEXT    NOT NULL,
+    PRIMARY KEY (Year, Source)
+);
+
+-- Until now, holidays always came from canada-holidays.ca for BC
+UPDATE days_off
+SET Source = 'canada-holidays.ca/BC'
+WHERE Reason == 'stat_holiday';
+
+INSERT INTO holiday_years (Year, Source, FetchedAt)
+SELECT DISTINCT CAST(strftime('%Y', Day) AS INTEGER), 'canada-holidays.ca/BC', datetime('now', 'localtime')
+FROM days_off
+WHERE Reason == 'stat_holiday';
@@ -0,0 +1,6 @@
+-- Upgrade from schema_v1.11 to schema_v1.12
+-- Days off can have a label, and can be only part of a day. StartOff and EndOff are the hours off
+-- as %H:%M:%S, and are NULL for a whole day off
+ALTER TABLE days_off ADD COLUMN Label TEXT NOT NULL DEFAULT '';
+ALTER TABLE days_off ADD COLUMN StartOff TEXT;
+ALTER TABLE days_off ADD COLUMN EndOff TEXT;
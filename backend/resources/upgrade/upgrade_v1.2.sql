@@ -0,0 +1,3 @@
+-- Upgrade from schema_v1.1 to schema_v1.2
+-- Tasks finished before this version have no record of when they were finished, so they are left NULL
+ALTER TABLE tasks ADD COLUMN FinishedDate TEXT;
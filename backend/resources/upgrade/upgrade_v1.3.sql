@@ -0,0 +1,12 @@
+-- Upgrade from schema_v1.2 to schema_v1.3
+-- History deliberately has no foreign key to tasks, so that it outlives deleted tasks
+CREATE TABLE task_history(
+	Task       INTEGER,
+	ChangedAt  TEXT,
+	ChangedBy  TEXT,
+	Action     TEXT CHECK(Action IN ('create', 'update', 'delete')),
+	Field      TEXT,
+	OldValue   TEXT,
+	NewValue   TEXT
+);
+CREATE INDEX task_history_by_task ON task_history(Task);
@@ -0,0 +1,3 @@
+-- Upgrade from schema_v1.3 to schema_v1.4
+-- Deleted tasks are kept in the trash until they are purged. NULL means the task is not deleted.
+ALTER TABLE tasks ADD COLUMN DeletedAt TEXT;
@@ -0,0 +1,7 @@
+-- Upgrade from schema_v1.4 to schema_v1.5
+-- Edits that can be undone or redone, as JSON. The top of each stack is the row with the highest EditID.
+CREATE TABLE undo_log (
+    EditID INTEGER PRIMARY KEY,
+    Stack  TEXT NOT NULL CHECK (Stack IN ('undo', 'redo')),
+    Edit   TEXT NOT NULL
+);
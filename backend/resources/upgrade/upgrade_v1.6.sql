@@ -0,0 +1,63 @@
+-- Upgrade from schema_v1.5 to schema_v1.6
+-- Full-text index of tasks, keyed by TaskID. Links holds the display text of the task's hyperlinks.
+CREATE VIRTUAL TABLE task_search USING fts5(Name, Notes, Category, Links);
+
+INSERT INTO task_search (rowid, Name, Notes, Category, Links)
+SELECT
+    TaskID,
+    Name,
+    Notes,
+    Category,
+    (SELECT group_concat(Display, ' ') FROM hyperlinks WHERE Task == TaskID)
+FROM tasks;
+
+CREATE TRIGGER task_search_insert_task
+AFTER INSERT ON tasks
+BEGIN
+    INSERT INTO task_search (rowid, Name, Notes, Category, Links)
+    VALUES (NEW.TaskID, NEW.Name, NEW.Notes, NEW.Category, NULL);
+END;
+
+CREATE TRIGGER task_search_update_task
+AFTER UPDATE OF Name, Notes, Category ON tasks
+BEGIN
+    UPDATE task_search
+    SET
+        Name =     NEW.Name,
+        Notes =    NEW.Notes,
+        Category = NEW.Category
+    WHERE rowid == NEW.TaskID;
+END;
+
+CREATE TRIGGER task_search_delete_task
+AFTER DELETE ON tasks
+BEGIN
+    DELETE FROM task_search WHERE rowid == OLD.TaskID;
+END;
+
+CREATE TRIGGER task_search_insert_link
+AFTER INSERT ON hyperlinks
+BEGIN
+    UPDATE task_search
+    SET Links = (SELECT group_concat(Display, ' ') FROM hyperlinks WHERE Task == NEW.Task)
+    WHERE rowid == NEW.Task;
+END;
+
+CREATE TRIGGER task_search_update_link
+AFTER UPDATE ON hyperlinks
+BEGIN
+    UPDATE task_search
+    SET Links = (SELECT group_concat(Display, ' ') FROM hyperlinks WHERE Task == OLD.Task)
+    WHERE rowid == OLD.Task;
+    UPDATE task_search
+    SET Links = (SELECT group_concat(Display, ' ') FROM hyperlinks WHERE Task == NEW.Task)
+    WHERE rowid == NEW.Task;
+END;
+
+CREATE TRIGGER task_search_delete_link
+AFTER DELETE ON hyperlinks
+BEGIN
+    UPDATE task_search
+    SET Links = (SELECT group_concat(Display, ' ') FROM hyperlinks WHERE Task == OLD.Task)
+    WHERE rowid == OLD.Task;
+END;
@@ -0,0 +1,3 @@
+-- Upgrade from schema_v1.6 to schema_v1.7
+-- Links are always looked up by task
+CREATE INDEX hyperlinks_by_task ON hyperlinks (Task);
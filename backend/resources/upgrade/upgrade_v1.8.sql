@@ -0,0 +1,30 @@
+-- Upgrade from schema_v1.7 to schema_v1.8
+-- Budget, Time and Used are stored in seconds rather than minutes, so that short timer sessions are not lost
+UPDATE tasks
+SET
+    Budget = Budget * 60,
+    Time =   Time * 60,
+    Used =   Used * 60;
+
+UPDATE task_history
+SET
+    OldValue = CAST(OldValue AS INTEGER) * 60,
+    NewValue = CAST(NewValue AS INTEGER) * 60
+WHERE Field IN ('Time', 'Used');
+
+-- Tasks in the undo log are stored the same way as in the tasks table
+UPDATE undo_log
+SET Edit = json_set(Edit,
+    '$.Task.before.time_budgeted', json_extract(Edit, '$.Task.before.time_budgeted') * 60,
+    '$.Task.before.time_needed',   json_extract(Edit, '$.Task.before.time_needed') * 60,
+    '$.Task.before.time_used',     json_extract(Edit, '$.Task.before.time_used') * 60
+)
+WHERE json_extract(Edit, '$.Task.before') IS NOT NULL;
+
+UPDATE undo_log
+SET Edit = json_set(Edit,
+    '$.Task.after.time_budgeted', json_extract(Edit, '$.Task.after.time_budgeted') * 60,
+    '$.Task.after.time_needed',   json_extract(Edit, '$.Task.after.time_needed') * 60,
+    '$.Task.after.time_used',     json_extract(Edit, '$.Task.after.time_used') * 60
+)
+WHERE json_extract(Edit, '$.Task.after') IS NOT NULL;
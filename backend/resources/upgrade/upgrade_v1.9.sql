@@ -0,0 +1,23 @@
+-- Upgrade from schema_v1.8 to schema_v1.9
+-- DueDate is split into a kind and a date, so that sorting by (DueKind, DueOn) matches DueDate's Ord:
+-- ASAP (0), then dates in order (1), then no due date (2)
+ALTER TABLE tasks ADD COLUMN DueKind INTEGER NOT NULL DEFAULT 0 CHECK (DueKind IN (0, 1, 2));
+ALTER TABLE tasks ADD COLUMN DueOn TEXT;
+
+UPDATE tasks
+SET
+    DueKind = CASE DueDate
+        WHEN 'ASAP' THEN 0
+        WHEN 'None' THEN 2
+        ELSE 1
+    END,
+    DueOn = CASE
+        WHEN DueDate IN ('ASAP', 'None') THEN NULL
+        ELSE DueDate
+    END;
+
+ALTER TABLE tasks DROP COLUMN DueDate;
+
+CREATE INDEX tasks_by_due_date    ON tasks (DueKind, DueOn);
+CREATE INDEX tasks_by_finished    ON tasks (Finished);
+CREATE INDEX tasks_by_next_action ON tasks (NextAction);
@@ -0,0 +1,186 @@
+//! Snapshots of a database, taken with `VACUUM INTO` by `database::Connection::backup`.
+//!
+//! Backups of `worklist.db` are kept in a `backups` directory beside it, named after the
+//! database and the time they were taken, e.g. `worklist-20240102-030405.db`. Snapshots taken
+//! before upgrading the schema are also labelled with the upgrade, e.g.
+//! `worklist-20240102-030405-before-v1.10.db`, and are never pruned.
+
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use chrono::{
+    Duration,
+    NaiveDateTime,
+};
+
+use crate::error::Result;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+/// The length of a timestamp formatted with `TIMESTAMP_FORMAT`
+const TIMESTAMP_WIDTH: usize = "YYYYmmdd-HHMMSS".len();
+
+/// Where backups of a database are kept, how often they are taken and how many are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPolicy {
+    pub directory: PathBuf,
+    /// The start of the name of every backup, i.e., the name of the database without its extension
+    pub name:      String,
+    /// `Connection::backup_if_due` does nothing if the newest backup is younger than this
+    pub interval:  Duration,
+    /// The most periodic backups kept. Older ones are deleted as new ones are taken.
+    pub keep:      usize,
+}
+
+impl BackupPolicy {
+    /// Pure
+    ///
+    /// Daily backups of the database at `database_path`, kept for a week in a `backups` directory
+    /// beside it
+    #[must_use] pub fn for_database(database_path: &str) -> Self {
+        let path = Path::new(database_path);
+
+        Self {
+            directory: path.parent().unwrap_or(Path::new("")).join("backups"),
+            name:      path.file_stem().map_or("worklist".into(), |s| s.to_string_lossy().into_owned()),
+            interval:  Duration::days(1),
+            keep:      7,
+        }
+    }
+
+    /// Pure
+    ///
+    /// The path of a backup taken at `taken_at`
+    #[must_use] pub fn path(&self, taken_at: NaiveDateTime, label: Option<&str>) -> PathBuf {
+        let timestamp = taken_at.format(TIMESTAMP_FORMAT);
+        self.directory.join(match label {
+            Some(label) => format!("{}-{timestamp}-{label}.db", self.name),
+            None        => format!("{}-{timestamp}.db", self.name),
+        })
+    }
+
+    /// Impure (reads filesystem)
+    ///
+    /// Returns the backups of this database, newest first. Files that are not named like a backup
+    /// are ignored.
+    /// # Errors
+    /// Returns an error if the backup directory exists but cannot be read
+    pub fn backups(&self) -> Result<Vec<Backup>> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if let Some(backup) = self.parse(&path) {
+                backups.push(backup);
+            }
+        }
+
+        backups.sort_by_key(|b| Reverse(b.taken_at));
+
+        Ok(backups)
+    }
+
+    /// Impure (writes filesystem)
+    ///
+    /// Deletes the oldest periodic backups, so that at most `keep` remain
+    /// # Errors
+    /// Returns an error if the backups cannot be listed or deleted
+    pub fn prune(&self) -> Result<()> {
+        for backup in self.backups()?.into_iter().filter(|b| b.label.is_none()).skip(self.keep) {
+            fs::remove_file(backup.path)?;
+        }
+
+        Ok(())
+    }
+
+    fn parse(&self, path: &Path) -> Option<Backup> {
+        let rest = path.file_name()?
+            .to_str()?
+            .strip_prefix(&self.name)?
+            .strip_prefix('-')?
+            .strip_suffix(".db")?;
+
+        // The timestamp is a fixed width, so the label is anything after it
+        let (timestamp, label) = match rest.split_at_checked(TIMESTAMP_WIDTH)? {
+            (timestamp, "") => (timestamp, None),
+            (timestamp, label) => (timestamp, Some(label.strip_prefix('-')?.to_string())),
+        };
+
+        Some(Backup {
+            path: path.to_path_buf(),
+            taken_at: NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?,
+            label,
+        })
+    }
+}
+
+/// A snapshot of a database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path:     PathBuf,
+    pub taken_at: NaiveDateTime,
+    /// Why the backup was taken, if it wasn't a periodic backup, e.g., `before-v1.10`
+    pub label:    Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(test: &str) -> BackupPolicy {
+        let directory = std::env::temp_dir().join(format!("beavor_{test}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        BackupPolicy {
+            directory,
+            name:     "worklist".into(),
+            interval: Duration::days(1),
+            keep:     2,
+        }
+    }
+
+    fn at(day: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(3, 4, 5).unwrap()
+    }
+
+    #[test]
+    fn test_backups_are_listed_newest_first() {
+        let policy = policy("backups_listed");
+        for (day, label) in [(1, None), (3, Some("before-v1.10")), (2, None)] {
+            fs::write(policy.path(at(day), label), "").unwrap();
+        }
+        fs::write(policy.directory.join("notes.txt"), "").unwrap();
+        fs::write(policy.directory.join("other-20240104-030405.db"), "").unwrap();
+
+        let backups = policy.backups().unwrap();
+
+        assert_eq!(backups.iter().map(|b| b.taken_at).collect::<Vec<_>>(), vec![at(3), at(2), at(1)]);
+        assert_eq!(backups[0].label.as_deref(), Some("before-v1.10"));
+        assert_eq!(backups[0].path, policy.directory.join("worklist-20240103-030405-before-v1.10.db"));
+        assert_eq!(backups[1].label, None);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_and_labelled_backups() {
+        let policy = policy("backups_pruned");
+        for day in 1..=4 {
+            fs::write(policy.path(at(day), None), "").unwrap();
+        }
+        fs::write(policy.path(at(1), Some("before-v1.10")), "").unwrap();
+
+        policy.prune().unwrap();
+
+        let remaining: Vec<(NaiveDateTime, Option<String>)> = policy.backups().unwrap()
+            .into_iter()
+            .map(|b| (b.taken_at, b.label))
+            .collect();
+        assert_eq!(remaining, vec![(at(4), None), (at(3), None), (at(1), Some("before-v1.10".into()))]);
+    }
+}
@@ -0,0 +1,75 @@
+//! Noticing when something else changes the database, e.g., a second beavor or a script
+
+use std::time::Duration;
+use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+
+use sqlx::{
+    Row,
+    sqlite::SqliteConnection,
+};
+
+use crate::error::Result;
+
+/// Waits for commits to a database made through any other connection to it. Created by
+/// `database::Connection::watch`.
+///
+/// This polls `PRAGMA data_version`, which changes whenever another connection commits, including
+/// the `Connection` it was created from. Its commits are told apart by counting them, so a change
+/// made by something else in the same interval as one made through it is not noticed.
+#[derive(Debug)]
+pub struct ChangeDetector {
+    conn:          SqliteConnection,
+    data_version:  i64,
+    interval:      Duration,
+    /// The commits made through the watched `Connection`, and how many of them had been made when
+    /// `data_version` last changed
+    local_commits: Arc<AtomicU64>,
+    commits_seen:  u64,
+}
+
+impl ChangeDetector {
+    pub(crate) async fn new(mut conn: SqliteConnection, interval: Duration, local_commits: Arc<AtomicU64>) -> Result<Self> {
+        Ok(Self {
+            data_version: data_version(&mut conn).await?,
+            conn,
+            interval,
+            commits_seen: local_commits.load(Ordering::SeqCst),
+            local_commits,
+        })
+    }
+
+    /// Impure (reads database)
+    ///
+    /// Returns once something other than the watched `Connection` has changed the database since
+    /// this was created or last returned, checking once per interval.
+    /// # Errors
+    /// Returns an error if the database cannot be read
+    pub async fn changed(&mut self) -> Result<()> {
+        loop {
+            tokio::time::sleep(self.interval).await;
+
+            let data_version = data_version(&mut self.conn).await?;
+            if data_version != self.data_version {
+                self.data_version = data_version;
+
+                let commits = self.local_commits.load(Ordering::SeqCst);
+                let committed_locally = commits != self.commits_seen;
+                self.commits_seen = commits;
+                if !committed_locally {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn data_version(conn: &mut SqliteConnection) -> Result<i64> {
+    Ok(sqlx::query("PRAGMA data_version")
+        .fetch_one(&mut *conn)
+        .await?
+        .get(0))
+}
@@ -1,114 +1,244 @@
 use std::str::FromStr;
-use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
 
 use sqlx::sqlite::{
+    Sqlite,
     SqlitePool,
+    SqlitePoolOptions,
     SqliteRow,
+    SqliteConnection,
     SqliteConnectOptions,
+    SqliteJournalMode,
 };
 use sqlx::{
     Row,
     ConnectOptions,
+    Connection as _,
+    QueryBuilder,
 };
 
 use crate::{
     Task,
     Hyperlink,
-    utils::parse_date,
+    migrations,
+    changes::ChangeDetector,
+    backup::{
+        Backup,
+        BackupPolicy,
+    },
+    history::{
+        self,
+        ChangeAction,
+        TaskChange,
+    },
+    undo::{
+        Edit,
+        Stack,
+        UNDO_LIMIT,
+    },
+    error::{
+        BeavorError,
+        Result,
+    },
+    utils::{
+        parse_date,
+        parse_datetime,
+        format_datetime,
+        now_datetime,
+        today_date,
+        current_user,
+    },
     DueDate,
+    due_date::{
+        DUE_ASAP,
+        DUE_DATE,
+        DUE_NEVER,
+    },
     Schedule,
-    schedule::WorkWeek,
+    schedule::{
+        WorkWeek,
+        HourRange,
+        SchedulingStrategy,
+    },
+    DayOff,
+    days_off::Reason,
+    HolidayProvider,
+    FetchedHolidays,
 };
 
 use chrono::{
     NaiveDate,
-    Datelike,
-    Local,
+    NaiveDateTime,
+    NaiveTime,
+    Timelike,
     Duration,
 };
 
-use serde::{
-    Serialize,
-    Deserialize,
-};
-
 impl TryFrom<SqliteRow> for Task {
-    type Error = anyhow::Error;
+    type Error = BeavorError;
+
+    fn try_from(row: SqliteRow) -> Result<Self> {
+        let id = row.try_get::<Option<u32>, &str>("TaskID")
+            .map_err(|e| BeavorError::invalid_task(None, "TaskID", e))?;
+
+        // Reads a column, blaming this task if it can't be read
+        let get_str = |column: &'static str| row.try_get::<String, &str>(column)
+            .map_err(|e| BeavorError::invalid_task(id, column, e));
+        let get_seconds = |column: &'static str| row.try_get::<i64, &str>(column)
+            .map(Duration::seconds)
+            .map_err(|e| BeavorError::invalid_task(id, column, e));
 
-    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
         Ok(Task {
-            category:                     row.get::<String, &str>("Category"),
-            finished:                     row.get::<bool,   &str>("Finished"),
-            name:                         row.get::<String, &str>("Name"),
-            _time_budgeted: Duration::minutes(row.get::<i64, &str>("Budget")),
-            time_needed:    Duration::minutes(row.get::<i64, &str>("Time")),
-            time_used:      Duration::minutes(row.get::<i64, &str>("Used")),
-            next_action_date: parse_date(&row.get::<String, &str>("NextAction"))?,
-            due_date:                     row.get::<String, &str>("DueDate").try_into()?,
-            notes:                        row.get::<String, &str>("Notes"),
-            id:                           row.get::<Option<u32>, &str>("TaskID"),
-            date_added:       parse_date(&row.get::<String, &str>("DateAdded"))?,
-            links:                        Vec::new(),
+            category:         get_str("Category")?,
+            finished:         row.try_get::<bool, &str>("Finished").map_err(|e| BeavorError::invalid_task(id, "Finished", e))?,
+            name:             get_str("Name")?,
+            _time_budgeted:   get_seconds("Budget")?,
+            time_needed:      get_seconds("Time")?,
+            time_used:        get_seconds("Used")?,
+            next_action_date: parse_date(&get_str("NextAction")?).map_err(|e| BeavorError::invalid_task(id, "NextAction", e))?,
+            due_date:         DueDate::from_columns(
+                row.try_get::<i64, &str>("DueKind").map_err(|e| BeavorError::invalid_task(id, "DueKind", e))?,
+                row.try_get::<Option<&str>, &str>("DueOn").map_err(|e| BeavorError::invalid_task(id, "DueOn", e))?
+            ).map_err(|e| BeavorError::invalid_task(id, "DueOn", e))?,
+            notes:            get_str("Notes")?,
+            finished_date:    row.try_get::<Option<String>, &str>("FinishedDate")
+                .map_err(|e| BeavorError::invalid_task(id, "FinishedDate", e))?
+                .map(|d| parse_date(&d))
+                .transpose()
+                .map_err(|e| BeavorError::invalid_task(id, "FinishedDate", e))?,
+            id,
+            date_added:       parse_date(&get_str("DateAdded")?).map_err(|e| BeavorError::invalid_task(id, "DateAdded", e))?,
+            links:            Vec::new(),
+            revision:         row.try_get::<u32, &str>("Revision").map_err(|e| BeavorError::invalid_task(id, "Revision", e))?,
         })
     }
 }
 
 impl TryFrom<SqliteRow> for Hyperlink {
-    type Error = std::convert::Infallible;
+    type Error = BeavorError;
+
+    fn try_from(row: SqliteRow) -> Result<Self> {
+        let task = row.try_get::<Option<u32>, &str>("Task").ok().flatten();
+
+        let invalid = |column: &'static str, e: sqlx::Error| BeavorError::InvalidRow {
+            table: "hyperlinks",
+            id: task,
+            column,
+            reason: e.to_string(),
+        };
 
-    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
         Ok(Hyperlink {
-            url:     row.get::<String, &str>("Url"),
-            display: row.get::<String, &str>("Display"),
-            id:      row.get::<u32, &str>("rowid") as usize,
+            url:     row.try_get::<String, &str>("Url").map_err(|e| invalid("Url", e))?,
+            display: row.try_get::<String, &str>("Display").map_err(|e| invalid("Display", e))?,
+            id:      row.try_get::<u32, &str>("rowid").map_err(|e| invalid("rowid", e))? as usize,
         })
     }
 }
 
-#[derive(PartialEq)]
-#[derive(Serialize, Deserialize)]
-struct Province {
-    id: String,
+/// Selects a page of finished tasks for `Connection::finished_tasks`.
+/// Each filter that is `None` matches every task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveFilter {
+    /// Earliest finished date, inclusive
+    pub from:      Option<NaiveDate>,
+    /// Latest finished date, inclusive
+    pub to:        Option<NaiveDate>,
+    pub category:  Option<String>,
+    /// Matched against the name and notes of each task
+    pub text:      Option<String>,
+    /// Zero-indexed
+    pub page:      u32,
+    pub page_size: u32,
 }
 
-#[derive(Serialize, Deserialize)]
-struct Holidays {
-    holidays: Vec<Holiday>,
+impl Default for ArchiveFilter {
+    fn default() -> Self {
+        Self {
+            from:      None,
+            to:        None,
+            category:  None,
+            text:      None,
+            page:      0,
+            page_size: 50,
+        }
+    }
 }
 
-#[allow(non_snake_case)]
-#[derive(Serialize, Deserialize)]
-struct Holiday {
-    provinces: Vec<Province>,
-    observedDate: String
+/// A task in the trash, and when it was put there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashedTask {
+    pub task:       Task,
+    pub deleted_at: NaiveDateTime,
 }
 
 #[derive(Debug, Clone)]
 pub struct Connection {
-    pool: SqlitePool,
+    pool:          SqlitePool,
+    /// The number of commits made through this connection and its clones, so that a
+    /// `ChangeDetector` can tell them apart from commits made by anything else
+    local_commits: Arc<AtomicU64>,
 }
 
 // TODO these methods should selectively take a &mut self when they modify the database, with some
 // sort of "dirty" flag. This would both allow caching (for a slight performance boost) AND let the
 // borrow checker monitor the state of the database itself, not just the connection to it
 impl Connection {
+    /// Opens the database and upgrades it to the latest schema version, if necessary.
+    /// Before upgrading, a snapshot of the database is saved with the backups described by
+    /// `BackupPolicy::for_database`.
     /// # Errors
     /// Will fail if a connection to the database cannot be established. This is generally if the
-    /// database file does not exist or is corrupted.
-    pub async fn new(database_path: &str) -> Result<Self, sqlx::Error> {
-        let pool = SqlitePool::connect(database_path).await?;
+    /// database file does not exist.
+    /// Returns `BeavorError::Corrupt` if the database is damaged. It can then be replaced with
+    /// `restore_from`.
+    /// Will also fail if the database was created by a newer version of beavor, or cannot be
+    /// upgraded.
+    pub async fn new(database_path: &str) -> Result<Self> {
+        // Write-ahead logging lets other programs read the database while beavor writes to it,
+        // and vice versa
+        let options = SqliteConnectOptions::from_str(database_path)?
+            .journal_mode(SqliteJournalMode::Wal);
+
+        // Migrate on a dedicated connection with foreign keys off, so that rebuilding a table
+        // doesn't cascade into the tables that reference it
+        let mut conn = options.clone()
+            .foreign_keys(false)
+            .connect()
+            .await?;
+        Self::check_integrity(&mut conn).await?;
+
+        // Keep a copy of the database as it was, in case the upgrade loses anything
+        if migrations::current_version(&mut conn).await? < migrations::latest_version() {
+            let label = format!("before-{}", migrations::MIGRATIONS.last().map_or("upgrade", |m| m.name));
+            Self::vacuum_into(&mut conn, &BackupPolicy::for_database(database_path), Some(&label)).await?;
+        }
+
+        migrations::migrate(&mut conn).await?;
+        conn.close().await?;
+
+        let pool = SqlitePool::connect_with(options).await?;
 
         sqlx::query!("PRAGMA foreign_keys=ON").execute(&pool).await?;
 
         Ok(Self {
             pool,
+            local_commits: Arc::default(),
         })
     }
 
     /// # Errors
     /// Will fail if a connection to the database cannot be established. This is generally if the
     /// database file does not exist or is corrupted.
-    pub async fn with_new_database(database_path: &str) -> Result<Self, sqlx::Error> {
+    pub async fn with_new_database(database_path: &str) -> Result<Self> {
         let mut conn = SqliteConnectOptions::from_str(database_path)?
             .create_if_missing(true)
             .connect()
@@ -119,22 +249,226 @@ impl Connection {
         sqlx::query_file!("resources/schema.sql")
             .execute(&mut conn)
             .await?;
+        // Upgrade it here, so that `new` doesn't snapshot an empty database before upgrading it
+        migrations::migrate(&mut conn).await?;
+        conn.close().await?;
+
+        Self::new(database_path).await
+    }
+
+    /// Replaces the database at `database_path` with a copy of `backup`, and opens it.
+    /// The database being replaced, if any, is kept beside it with `.damaged-<time>` added to
+    /// its name.
+    /// # Errors
+    /// Returns `BeavorError::Corrupt` if the backup is itself damaged, in which case nothing is
+    /// replaced.
+    /// Will also fail if the files cannot be moved or copied, or the restored database cannot be
+    /// opened.
+    pub async fn restore_from(backup: &Path, database_path: &str) -> Result<Self> {
+        // Check the backup before replacing anything with it
+        Self::check_backup(backup).await?;
+
+        let path = Path::new(database_path);
+        if path.exists() {
+            // Keep the damaged database, in case anything can be recovered from it
+            let mut damaged = path.as_os_str().to_owned();
+            damaged.push(format!(".damaged-{}", now_datetime().format("%Y%m%d-%H%M%S")));
+            fs::rename(path, damaged)?;
+        }
+
+        // A journal left behind by the damaged database must not be applied to the backup
+        for journal in ["-wal", "-shm", "-journal"] {
+            match fs::remove_file(format!("{database_path}{journal}")) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => (),
+            }
+        }
+
+        fs::copy(backup, path)?;
 
         Self::new(database_path).await
     }
 
-    /// # Panics
-    /// Panics if any database query fails, or if the database contains invalid tasks.
-    pub async fn create_task(&self, task: &Task) -> Task {
+    /// Checks `backup` for damage, without restoring it
+    /// # Errors
+    /// Returns `BeavorError::Corrupt` if the backup is damaged, or another error if it cannot be
+    /// opened.
+    pub async fn check_backup(backup: &Path) -> Result<()> {
+        // This can't be read-only, because checking the search index writes to it
+        let mut conn = SqliteConnectOptions::new()
+            .filename(backup)
+            .connect()
+            .await?;
+        Self::check_integrity(&mut conn).await?;
+        conn.close().await?;
+
+        Ok(())
+    }
+
+    /// Returns the newest backup described by `policy` that isn't damaged, i.e., the one to
+    /// restore from. Nothing if there are no backups, or all of them are damaged.
+    /// # Errors
+    /// Returns an error if the backups cannot be listed
+    pub async fn latest_good_backup(policy: &BackupPolicy) -> Result<Option<Backup>> {
+        for backup in policy.backups()? {
+            if Self::check_backup(&backup.path).await.is_ok() {
+                return Ok(Some(backup));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns a detector for commits made to the database by anything else, checking every
+    /// `interval`.
+    /// This does not work for databases created by `in_memory`, which nothing else can open.
+    /// # Errors
+    /// Returns an error if a new connection to the database cannot be opened
+    pub async fn watch(&self, interval: std::time::Duration) -> Result<ChangeDetector> {
+        let conn = self.pool.connect_options().connect().await?;
+        ChangeDetector::new(conn, interval, Arc::clone(&self.local_commits)).await
+    }
+
+    /// Commits `tx`, counting it as made through this connection so that a `ChangeDetector` from
+    /// `watch` ignores it
+    async fn commit(&self, tx: sqlx::Transaction<'_, Sqlite>) -> Result<()> {
+        // Counted first, so the detector never sees the commit before the count. It is taken back
+        // if the commit fails, before the database could have changed.
+        self.local_commits.fetch_add(1, Ordering::SeqCst);
+        let committed = tx.commit().await;
+        if committed.is_err() {
+            self.local_commits.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        Ok(committed?)
+    }
+
+    /// Saves a snapshot of the database as described by `policy`, then deletes the oldest
+    /// backups beyond those it keeps.
+    /// # Errors
+    /// Returns an error if the backup directory cannot be created, the snapshot cannot be
+    /// written, or old backups cannot be deleted.
+    pub async fn backup(&self, policy: &BackupPolicy) -> Result<Backup> {
+        let mut conn = self.pool.acquire().await?;
+        let backup = Self::vacuum_into(&mut conn, policy, None).await?;
+        drop(conn);
+
+        policy.prune()?;
+
+        Ok(backup)
+    }
+
+    /// Calls `backup` if there are no backups at least `policy.interval` younger than now.
+    /// Returns the backup taken, if any.
+    /// # Errors
+    /// Returns an error if the backups cannot be listed, or `backup` fails.
+    pub async fn backup_if_due(&self, policy: &BackupPolicy) -> Result<Option<Backup>> {
+        let latest = policy.backups()?
+            .into_iter()
+            .find(|b| b.label.is_none());
+
+        if latest.is_some_and(|b| b.taken_at + policy.interval > now_datetime()) {
+            Ok(None)
+        } else {
+            self.backup(policy).await.map(Some)
+        }
+    }
+
+    async fn vacuum_into(conn: &mut SqliteConnection, policy: &BackupPolicy, label: Option<&str>) -> Result<Backup> {
+        fs::create_dir_all(&policy.directory)?;
+
+        let taken_at = now_datetime();
+        let path = policy.path(taken_at, label);
+
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(Backup {
+            path,
+            // The file name only keeps whole seconds
+            taken_at: taken_at.with_nanosecond(0).unwrap_or(taken_at),
+            label: label.map(str::to_string),
+        })
+    }
+
+    /// Returns `BeavorError::Corrupt` describing what is wrong if the database is damaged
+    async fn check_integrity(conn: &mut SqliteConnection) -> Result<()> {
+        let problems: Vec<String> = sqlx::query("PRAGMA quick_check")
+            .fetch_all(&mut *conn)
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        if problems == ["ok"] {
+            Ok(())
+        } else {
+            Err(BeavorError::Corrupt(problems.join("; ")))
+        }
+    }
+
+    /// Creates a new database that only exists in memory, and is gone once the connection is
+    /// dropped. It has the latest schema, and is otherwise empty.
+    /// This is intended for tests.
+    /// # Errors
+    /// Will fail if the schema cannot be applied, which should never happen.
+    pub async fn in_memory() -> Result<Self> {
+        // Every connection to :memory: opens a different database, so the pool must hold exactly
+        // one connection, for as long as the pool lives
+        let pool = SqlitePoolOptions::new()
+            .min_connections(1)
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?.foreign_keys(false))
+            .await?;
+
+        let mut conn = pool.acquire().await?;
+        sqlx::query_file!("resources/schema.sql")
+            .execute(&mut *conn)
+            .await?;
+        migrations::migrate(&mut conn).await?;
+        sqlx::query!("PRAGMA foreign_keys=ON").execute(&mut *conn).await?;
+        drop(conn);
+
+        Ok(Self {
+            pool,
+            local_commits: Arc::default(),
+        })
+    }
+
+    /// The task and its links are inserted in a single transaction, so either all or none of them
+    /// are written.
+    /// A finished task is recorded as finished today, unless it already has a `finished_date`.
+    /// # Errors
+    /// Returns an error if any database query fails, or if the inserted task cannot be read back.
+    pub async fn create_task(&self, task: &Task) -> Result<Task> {
+        let mut tx = self.pool.begin().await?;
+
+        let created = Self::insert_task(&mut tx, task).await?;
+        Self::record_edit(&mut tx, &Edit::Task { before: None, after: Some(created.clone()) }).await?;
+
+        self.commit(tx).await?;
+
+        Ok(created)
+    }
+
+    /// Inserts the task and its links, and returns it as written.
+    /// This does not commit, so it should be called within a transaction.
+    async fn insert_task(conn: &mut SqliteConnection, task: &Task) -> Result<Task> {
         // These must be stored so that they are not dropped in-between
         // the calls to query! and .execute
-        let due_date_str = task.due_date.to_string();
+        let (due_kind, due_on) = task.due_date.to_columns();
         let next_action_str = DueDate::Date(task.next_action_date).to_string();
         let date_added_str = DueDate::Date(task.date_added).to_string();
+        let finished_date_str = task.finished
+            .then(|| task.finished_date.unwrap_or_else(today_date).to_string());
 
-        let time_budgeted = task.time_needed.num_minutes(); // When creating a new task, save the initial time_needed estimate as time_budgeted
-        let time_needed = task.time_needed.num_minutes();
-        let time_used = task.time_used.num_minutes();
+        let time_budgeted = task.time_needed.num_seconds(); // When creating a new task, save the initial time_needed estimate as time_budgeted
+        let time_needed = task.time_needed.num_seconds();
+        let time_used = task.time_used.num_seconds();
 
         let new_rowid: i64 = sqlx::query!("
             INSERT INTO tasks
@@ -146,9 +480,11 @@ impl Connection {
                     Time,
                     Used,
                     NextAction,
-                    DueDate,
+                    DueKind,
+                    DueOn,
                     Notes,
-                    DateAdded
+                    DateAdded,
+                    FinishedDate
                 )
             VALUES
                 (
@@ -161,6 +497,8 @@ impl Connection {
                     ?,
                     ?,
                     ?,
+                    ?,
+                    ?,
                     ?
                 )
         ",
@@ -171,59 +509,88 @@ impl Connection {
             time_needed,
             time_used,
             next_action_str,
-            due_date_str,
+            due_kind,
+            due_on,
             task.notes,
             date_added_str,
+            finished_date_str,
         )
-            .execute(&self.pool)
-            .await
-            .expect("Should be able to insert Task into database")
+            .execute(&mut *conn)
+            .await?
             .last_insert_rowid();
 
-        self.insert_hyperlinks(&task.links, new_rowid).await;
+        Self::insert_hyperlinks(conn, &task.links, new_rowid).await?;
 
-        // TODO this doesn't use query! because I'm too lazy to figure out how to annotate the
-        // return type of query! to write an impl From<T> for Task
-        sqlx::query("
-            SELECT *
-            FROM tasks
-            WHERE TaskID == ?
-        ")
-            .bind(new_rowid)
-            .fetch_one(&self.pool)
-            .await
-            .expect("Should have inserted and retrieved a task")
-            .try_into()
-            .expect("Database should contain valid Tasks only")
+        let created = Self::fetch_task(conn, new_rowid).await?;
+        Self::record_history(conn, new_rowid, ChangeAction::Create, None, Some(&created)).await?;
+
+        Ok(created)
     }
 
-    /// # Panics
-    /// Panics if any database query fails, or if the task has an invalid id.
+    /// The task and its links are updated in a single transaction, so a failure part-way through
+    /// leaves the stored task exactly as it was.
+    /// `FinishedDate` is maintained here rather than taken from the task: it is set to today when
+    /// the task becomes finished, kept while it stays finished, and cleared if it is reopened.
+    /// Returns the task as it is now stored, with its new revision.
     /// # Errors
-    /// This returns a `RowNotFound` error if the update step fails to update any rows.
-    /// This indicates that no task with an id matching the passed task exists in the database.
-    pub async fn update_task(&self, task: &Task) -> Result<(), sqlx::Error> {
+    /// This returns a `NotFound` error if the task has no id, or if the update step fails to
+    /// update any rows. This indicates that no task with an id matching the passed task exists in
+    /// the database.
+    /// Returns a `Conflict` error if the stored task has been updated since `task` was read, i.e.,
+    /// their revisions differ. Nothing is written.
+    /// Also returns an error if any database query fails.
+    pub async fn update_task(&self, task: &Task) -> Result<Task> {
+        let mut tx = self.pool.begin().await?;
+
+        let (before, after) = Self::write_task(&mut tx, task, true).await?;
+        Self::record_edit(&mut tx, &Edit::Task { before: Some(before), after: Some(after.clone()) }).await?;
+
+        self.commit(tx).await?;
+
+        Ok(after)
+    }
+
+    /// Overwrites the stored task and its links, and returns the task before and after the write.
+    /// If `check_revision`, the write is refused with a `Conflict` unless the stored task has the
+    /// same revision as `task`.
+    /// This does not commit, so it should be called within a transaction.
+    async fn write_task(conn: &mut SqliteConnection, task: &Task, check_revision: bool) -> Result<(Task, Task)> {
+        let Some(id) = task.id else {
+            return Err(BeavorError::NotFound(format!("Task '{}' has no id, so it cannot be updated", task.name)));
+        };
+
         // These must be stored so that they are not dropped in-between
         // the calls to query! and .execute
         let next_action_str = DueDate::Date(task.next_action_date).to_string();
-        let due_date_str = task.due_date.to_string();
+        let (due_kind, due_on) = task.due_date.to_columns();
+
+        let time_needed = task.time_needed.num_seconds();
+        let time_used = task.time_used.num_seconds();
+        let today_str = today_date().to_string();
 
-        let time_needed = task.time_needed.num_minutes();
-        let time_used = task.time_used.num_minutes();
+        let old = Self::fetch_task(conn, id.into()).await?;
 
+        // NOTE Within SET, `Finished` refers to the value before this update
         if sqlx::query!("
             UPDATE tasks
             SET
-                Category =    ?,
-                Finished =    ?,
-                Name =        ?,
-                Time =        ?,
-                Used =        ?,
-                NextAction =  ?,
-                DueDate =     ?,
-                Notes =       ?
+                Category =     ?,
+                Finished =     ?,
+                Name =         ?,
+                Time =         ?,
+                Used =         ?,
+                NextAction =   ?,
+                DueKind =      ?,
+                DueOn =        ?,
+                Notes =        ?,
+                FinishedDate = CASE
+                    WHEN NOT ? THEN NULL
+                    WHEN Finished THEN FinishedDate
+                    ELSE ?
+                END,
+                Revision =     Revision + 1
             WHERE
-                TaskID == ?
+                TaskID == ? AND DeletedAt IS NULL AND (NOT ? OR Revision == ?)
         ",
             task.category,
             task.finished,
@@ -231,15 +598,35 @@ impl Connection {
             time_needed,
             time_used,
             next_action_str,
-            due_date_str,
+            due_kind,
+            due_on,
             task.notes,
+            task.finished,
+            today_str,
             task.id,
+            check_revision,
+            task.revision,
         )
-            .execute(&self.pool)
-            .await
-            .expect("Should be able to update task")
+            .execute(&mut *conn)
+            .await?
             .rows_affected() != 1 {
-                return Err(sqlx::Error::RowNotFound)
+                let exists = sqlx::query!("
+                    SELECT TaskID
+                    FROM tasks
+                    WHERE TaskID == ? AND DeletedAt IS NULL
+                ",
+                    task.id,
+                )
+                    .fetch_optional(&mut *conn)
+                    .await?
+                    .is_some();
+
+                // If it wasn't updated but it's there, it must have been at another revision
+                return Err(if exists {
+                    BeavorError::Conflict(Box::new(old))
+                } else {
+                    BeavorError::NotFound(format!("No task with TaskID {id}"))
+                });
             }
 
         sqlx::query!("
@@ -248,264 +635,2232 @@ impl Connection {
         ",
             task.id,
         )
-            .execute(&self.pool)
-            .await
-            .expect("Should be able to delete links");
+            .execute(&mut *conn)
+            .await?;
 
-        self.insert_hyperlinks(&task.links, task.id.expect("Task id must not be None").try_into().expect("A valid id will always fit in an i64")).await;
-        Ok(())
-    }
+        Self::insert_hyperlinks(conn, &task.links, id.into()).await?;
 
-    async fn insert_hyperlinks(&self, links: &Vec<Hyperlink>, task_id: i64) {
-        for h in links {
-            sqlx::query!("
-                INSERT INTO hyperlinks (Url, Display, Task)
-                VALUES(?,?,?)
-            ",
-                h.url,
-                h.display,
-                task_id,
-            )
-                .execute(&self.pool)
-                .await
-                .expect("Should be able to insert new link");
-        }
-    }
+        let new = Self::fetch_task(conn, id.into()).await?;
+        Self::record_history(conn, id.into(), ChangeAction::Update, Some(&old), Some(&new)).await?;
 
-    /// Note: this deliberately takes ownership of task, because it will be deleted from the
-    /// database afterward and taking ownership prevents references to the nonexistent task from surviving.
-    /// This is not necessary for memory-safety, but for providing some level of confidence about
-    /// state consistency throughout the application.
-    /// # Panics
-    /// Panics if any database query fails.
-    pub async fn delete_task(&self, task: Task) {
-        // Note that hyperlinks are ON DELETE CASCADE, so do not need to be deleted manually
-        sqlx::query!("
-            DELETE
-            FROM tasks
-            WHERE TaskID == ?
-        ",
-            task.id
-        )
-            .execute(&self.pool)
-            .await
-            .expect("Should be able do delete task");
+        Ok((old, new))
     }
 
-    /// # Panics
-    /// Panics if any database query fails, or if the database contains invalid tasks or invalid links.
-    pub async fn open_tasks(&self) -> Vec<Task> {
-        
+    /// Reads a task and its links. This can be called within a transaction, to see its writes.
+    async fn fetch_task(conn: &mut SqliteConnection, id: i64) -> Result<Task> {
         // TODO this doesn't use query! because I'm too lazy to figure out how to annotate the
         // return type of query! to write an impl From<T> for Task
-        let mut tasks: Vec<Task> = sqlx::query("
+        let mut task: Task = sqlx::query("
             SELECT *
             FROM tasks
-            WHERE Finished == false
-            ORDER BY DueDate
+            WHERE TaskID == ?
         ")
-            .fetch_all(&self.pool)
-            .await
-            .expect("Should be able to get tasks")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| BeavorError::NotFound(format!("No task with TaskID {id}")))?
+            .try_into()?;
+
+        task.links = sqlx::query("
+            SELECT *, rowid
+            FROM hyperlinks
+            WHERE Task == ?
+        ")
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?
             .into_iter()
-            .map(|r: SqliteRow| Task::try_from(r).expect("Database should hold valid Tasks"))
-            .collect();
+            .map(Hyperlink::try_from)
+            .collect::<Result<_>>()?;
 
-        for task in &mut tasks {
-            task.links = sqlx::query("
-                SELECT *, rowid
-                FROM hyperlinks
-                WHERE Task == ?
-             ")
-                .bind(task.id)
-                .fetch_all(&self.pool)
-                .await
-                .expect("Should be able to get hyperlinks for task")
-                .into_iter()
-                .map(|r: SqliteRow| Hyperlink::try_from(r).expect("Database should hold valid links"))
-                .collect();
+        Ok(task)
+    }
+
+    /// Appends the fields that differ between `old` and `new` to the history of the task.
+    /// This does not commit, so it should be called within the transaction that writes the task.
+    async fn record_history(conn: &mut SqliteConnection, task_id: i64, action: ChangeAction, old: Option<&Task>, new: Option<&Task>) -> Result<()> {
+        let changes = history::diff(old, new);
+        if changes.is_empty() {
+            return Ok(());
         }
 
-        tasks.sort_by(|a,b| a.due_date.cmp(&b.due_date));
+        let changed_at = format_datetime(now_datetime());
+        let changed_by = current_user();
+        let action = action.to_string();
+
+        QueryBuilder::<Sqlite>::new("INSERT INTO task_history (Task, ChangedAt, ChangedBy, Action, Field, OldValue, NewValue) ")
+            .push_values(changes, |mut row, change| {
+                row.push_bind(task_id)
+                    .push_bind(changed_at.clone())
+                    .push_bind(changed_by.clone())
+                    .push_bind(action.clone())
+                    .push_bind(change.field)
+                    .push_bind(change.old_value)
+                    .push_bind(change.new_value);
+            })
+            .build()
+            .execute(conn)
+            .await?;
 
-        tasks
+        Ok(())
     }
 
-    /// # Panics
-    /// Panics if any database query fails, or if any row has a non-string category.
-    #[allow(non_snake_case)]
-    pub async fn categories(&self) -> Vec<String> {
-        sqlx::query!("
-            SELECT DISTINCT Category
-            FROM tasks
-            ORDER BY Category
+    /// Returns every recorded change to a task, oldest first.
+    /// History is kept after a task is deleted.
+    /// # Errors
+    /// Returns an error if the query fails, or if the history contains invalid rows.
+    pub async fn task_history(&self, id: u32) -> Result<Vec<TaskChange>> {
+        let invalid = |column: &'static str, reason: String| BeavorError::InvalidRow {
+            table: "task_history",
+            id: Some(id),
+            column,
+            reason,
+        };
+
+        sqlx::query("
+            SELECT *
+            FROM task_history
+            WHERE Task == ?
+            ORDER BY rowid
         ")
+            .bind(id)
             .fetch_all(&self.pool)
-            .await
-            .expect("Should be able to get categories")
+            .await?
             .into_iter()
-            .map(|r| r.Category.expect("Each category should be a string"))
+            .map(|row| Ok(TaskChange {
+                task_id:    id,
+                changed_at: parse_datetime(&row.try_get::<String, &str>("ChangedAt")?)
+                    .map_err(|e| invalid("ChangedAt", e.to_string()))?,
+                changed_by: row.try_get("ChangedBy")?,
+                action:     row.try_get::<String, &str>("Action")?
+                    .parse()
+                    .map_err(|e| invalid("Action", e))?,
+                field:      row.try_get("Field")?,
+                old_value:  row.try_get("OldValue")?,
+                new_value:  row.try_get("NewValue")?,
+            }))
             .collect()
     }
 
-    /// # Panics
-    /// Panics if any database query fails.
-    ///
-    /// # Errors
-    /// Returns an error if unable to connect to the holiday server or unable to parse the
-    /// response.
-    pub async fn try_update_holidays(&self) -> Result<()> {
-        // If database already has holidays from the current year, exit
-        if self.holidays()
-            .await
-            .iter()
-            .filter(|h| h.year() == Local::now().year())
-            .peekable()
-            .peek()
-            .is_some()
-        {
-            return Ok(())
+    /// Inserts all of `links` for the task in a single statement.
+    /// This does not commit, so it should be called within the transaction that writes the task.
+    async fn insert_hyperlinks(conn: &mut SqliteConnection, links: &[Hyperlink], task_id: i64) -> Result<()> {
+        if links.is_empty() {
+            return Ok(());
         }
 
-
-        // If database doesn't have the holidays for this year, get them
-        // and store them in the database
-        let response: String = reqwest::get("https://canada-holidays.ca/api/v1/holidays")
-            .await?
-            .text()
+        QueryBuilder::<Sqlite>::new("INSERT INTO hyperlinks (Url, Display, Task) ")
+            .push_values(links, |mut row, h| {
+                row.push_bind(&h.url)
+                    .push_bind(&h.display)
+                    .push_bind(task_id);
+            })
+            .build()
+            .execute(conn)
             .await?;
 
-        let holiday_dates: Vec<NaiveDate> = serde_json::from_str::<Holidays>(&response)?
-            .holidays
-            .iter()
-            .filter(|h| h.provinces.contains(&Province {id: "BC".to_string()}))
-            .map(|h| h.observedDate.parse::<NaiveDate>())
-            .collect::<Result<Vec<NaiveDate>, _>>()?;
+        Ok(())
+    }
+
+    /// Moves the task to the trash. It can be brought back with `restore_task` until it is
+    /// removed for good by `purge_trash`.
+    ///
+    /// Note: this deliberately takes ownership of task, because it will be deleted from the
+    /// database afterward and taking ownership prevents references to the nonexistent task from surviving.
+    /// This is not necessary for memory-safety, but for providing some level of confidence about
+    /// state consistency throughout the application.
+    /// # Errors
+    /// Returns a `NotFound` error if no task with a matching id exists in the database, or an
+    /// error if the query fails.
+    pub async fn delete_task(&self, task: Task) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
 
-            for d in holiday_dates {
-                let date_string = d.to_string();
+        let deleted = Self::trash_task(&mut tx, &task).await?;
+        Self::record_edit(&mut tx, &Edit::Task { before: Some(deleted), after: None }).await?;
 
-                sqlx::query!("
-                    INSERT INTO days_off
-                        (
-                            Day,
-                            Reason
-                        )
-                    VALUES
-                        (
-                            ?,
-                            'stat_holiday'
-                        )
-                    ",
-                    date_string
-                )
-                    .execute(&self.pool)
-                    .await
-                    .expect("Should be able to add the stat holiday");
-            }
+        self.commit(tx).await?;
 
         Ok(())
     }
 
-    /// # Panics
-    /// Panics if any database query fails.
-    pub async fn add_vacation_day(&self, date: &NaiveDate) {
-        let date_string = date.to_string();
-        sqlx::query!("
-            INSERT INTO days_off
-                (
-                    Day,
-                    Reason
-                )
-            VALUES
-                (
-                    ?,
-                    'vacation'
-                )
-            ",
-            date_string
-        )
-            .execute(&self.pool)
-            .await
-            .expect("Should be able to add vacation day");
-    }
+    /// Moves the task to the trash, and returns it as it was before.
+    /// This does not commit, so it should be called within a transaction.
+    async fn trash_task(conn: &mut SqliteConnection, task: &Task) -> Result<Task> {
+        let Some(id) = task.id else {
+            return Err(BeavorError::NotFound(format!("Task '{}' has no id, so it cannot be deleted", task.name)));
+        };
 
-    /// # Panics
-    /// Panics if any database query fails.
-    pub async fn delete_vacation_day(&self, date: &NaiveDate) {
-        let date_string = date.to_string();
+        let old = Self::fetch_task(conn, id.into()).await?;
+        let deleted_at = format_datetime(now_datetime());
 
-        sqlx::query!("
-            DELETE
-            FROM days_off
-            WHERE Day == ? AND Reason=='vacation'
+        if sqlx::query!("
+            UPDATE tasks
+            SET DeletedAt = ?
+            WHERE TaskID == ? AND DeletedAt IS NULL
         ",
-            date_string
+            deleted_at,
+            task.id
         )
-            .execute(&self.pool)
-            .await
-            .expect("Should be able do delete vacation day");
-    }
+            .execute(&mut *conn)
+            .await?
+            .rows_affected() != 1 {
+                return Err(BeavorError::NotFound(format!("Task '{}' does not exist, so it cannot be deleted", task.name)))
+            }
 
-    /// # Panics
-    /// Panics if any database query fails, or `days_off` contains invalid dates.
-    #[allow(non_snake_case)]
-    pub async fn vacation_days(&self) -> Vec<NaiveDate> {
-        sqlx::query!("
-            SELECT Day
-            FROM days_off
-            WHERE Reason == 'vacation'
-            ORDER BY Day
-        ")
-            .fetch_all(&self.pool)
-            .await
-            .expect("Should be able to get vacation days")
-            .into_iter()
-            .map(|record|
-                 record.Day.expect("Day is a field in days_off")
-                 .parse::<NaiveDate>().expect("days_off should contain valid dates")
-            )
-            .collect()
-    }
+        Self::record_history(conn, id.into(), ChangeAction::Delete, Some(&old), None).await?;
 
-    /// # Panics
-    /// Panics if any database query fails, or `days_off` contains invalid dates.
-    #[allow(non_snake_case)]
-    pub async fn holidays(&self) -> Vec<NaiveDate> {
-        sqlx::query!("
-            SELECT Day
-            FROM days_off
-            WHERE Reason == 'stat_holiday'
-            ORDER BY Day
-        ")
-            .fetch_all(&self.pool)
-            .await
-            .expect("Should be able to get stat holidays")
-            .into_iter()
-            .map(|record|
-                 record.Day.expect("Day is a field in days_off")
-                 .parse::<NaiveDate>().expect("days_off should contain valid dates")
-            )
-            .collect()
+        Ok(old)
     }
 
-    /// # Panics
-    /// Panics if unable to update holidays.
-    pub async fn days_off(&self) -> Vec<NaiveDate> {
-        let mut days_off = Vec::new();
+    /// Moves a task into another database, e.g., one belonging to a different profile, and returns
+    /// the task as it was created there.
+    /// The original is moved to the trash of this database, so both moves can be undone.
+    /// The two databases cannot share a transaction. If the original cannot be moved to the trash,
+    /// the copy is removed from `destination` again, along with its history and undo log entries,
+    /// so that the task does not end up in both.
+    /// # Errors
+    /// Returns a `NotFound` error if the task does not exist in this database, or an error if any
+    /// query fails.
+    pub async fn move_task(&self, task: Task, destination: &Connection) -> Result<Task> {
+        let moved = destination.create_task(&Task {
+            id: None,
+            ..task.clone()
+        }).await?;
 
-        self.try_update_holidays().await.unwrap();
-        days_off.append(&mut self.holidays().await);
-        days_off.append(&mut self.vacation_days().await);
+        if let Err(e) = self.delete_task(task).await {
+            destination.discard_task(&moved).await?;
+            return Err(e);
+        }
 
-        days_off
+        Ok(moved)
     }
 
-    pub async fn schedule (&self, work_week: WorkWeek, tasks: &Vec<Task>) -> Schedule {
-        Schedule::new(
-            self.days_off().await,
-            tasks,
-            work_week,
+    /// Removes a task that should never have been created for good, with its links, its history
+    /// and the edits to it in the undo log.
+    async fn discard_task(&self, task: &Task) -> Result<()> {
+        // A task without an id was never written
+        let Some(id) = task.id else {
+            return Ok(());
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        // Note that hyperlinks are ON DELETE CASCADE, so do not need to be deleted manually
+        sqlx::query!("
+            DELETE
+            FROM tasks
+            WHERE TaskID == ?
+        ",
+            id
         )
+            .execute(&mut *tx)
+            .await?;
+        Self::forget_tasks(&mut tx, &[id]).await?;
+
+        self.commit(tx).await?;
+
+        Ok(())
+    }
+
+    /// Impure (writes to the database)
+    ///
+    /// Deletes the history of tasks that no longer exist, and the edits to them in the undo log.
+    /// The database may reuse the ids of deleted tasks, so these would otherwise be taken for the
+    /// history of, or undone onto, a new task.
+    async fn forget_tasks(conn: &mut SqliteConnection, ids: &[u32]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        for id in ids {
+            sqlx::query!("
+                DELETE
+                FROM task_history
+                WHERE Task == ?
+            ",
+                id
+            )
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        let edits = sqlx::query!("
+            SELECT EditID, Edit
+            FROM undo_log
+        ")
+            .fetch_all(&mut *conn)
+            .await?;
+        for edit in edits {
+            if Edit::from_json(&edit.Edit)?.task_id().is_some_and(|id| ids.contains(&id)) {
+                sqlx::query!("
+                    DELETE
+                    FROM undo_log
+                    WHERE EditID == ?
+                ",
+                    edit.EditID
+                )
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the tasks in the trash, most recently deleted first
+    /// # Errors
+    /// Returns an error if any database query fails, or if the database contains invalid tasks or invalid links.
+    pub async fn trashed_tasks(&self) -> Result<Vec<TrashedTask>> {
+        let rows = sqlx::query("
+            SELECT *
+            FROM tasks
+            WHERE DeletedAt IS NOT NULL
+            ORDER BY DeletedAt DESC, TaskID DESC
+        ")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut deleted_at = Vec::with_capacity(rows.len());
+        let mut tasks = Vec::with_capacity(rows.len());
+        for row in rows {
+            let deleted = row.try_get::<String, &str>("DeletedAt")?;
+            let task = Task::try_from(row)?;
+            deleted_at.push(parse_datetime(&deleted).map_err(|e| BeavorError::invalid_task(task.id, "DeletedAt", e))?);
+            tasks.push(task);
+        }
+
+        self.load_links(&mut tasks).await?;
+
+        Ok(tasks.into_iter()
+            .zip(deleted_at)
+            .map(|(task, deleted_at)| TrashedTask { task, deleted_at })
+            .collect())
+    }
+
+    /// Takes a task back out of the trash.
+    /// This is recorded in the task's history as creating it again.
+    /// # Errors
+    /// Returns a `NotFound` error if the task is not in the trash, or an error if any query fails.
+    pub async fn restore_task(&self, id: u32) -> Result<Task> {
+        let mut tx = self.pool.begin().await?;
+
+        let restored = Self::untrash_task(&mut tx, id).await?;
+        Self::record_edit(&mut tx, &Edit::Task { before: None, after: Some(restored.clone()) }).await?;
+
+        self.commit(tx).await?;
+
+        Ok(restored)
+    }
+
+    /// Takes the task out of the trash, and returns it.
+    /// This does not commit, so it should be called within a transaction.
+    async fn untrash_task(conn: &mut SqliteConnection, id: u32) -> Result<Task> {
+        if sqlx::query!("
+            UPDATE tasks
+            SET DeletedAt = NULL
+            WHERE TaskID == ? AND DeletedAt IS NOT NULL
+        ",
+            id
+        )
+            .execute(&mut *conn)
+            .await?
+            .rows_affected() != 1 {
+                return Err(BeavorError::NotFound(format!("No task with TaskID {id} in the trash")))
+            }
+
+        let restored = Self::fetch_task(conn, id.into()).await?;
+        Self::record_history(conn, id.into(), ChangeAction::Create, None, Some(&restored)).await?;
+
+        Ok(restored)
+    }
+
+    /// Permanently deletes tasks that have been in the trash for longer than `retention`.
+    /// Returns the number of tasks deleted.
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn purge_trash(&self, retention: Duration) -> Result<u64> {
+        // Timestamps are stored as %F %H:%M:%S strings, so they compare correctly as text
+        let cutoff = format_datetime(now_datetime() - retention);
+
+        let mut tx = self.pool.begin().await?;
+
+        let ids = sqlx::query!("
+            SELECT TaskID
+            FROM tasks
+            WHERE DeletedAt IS NOT NULL AND DeletedAt < ?
+        ",
+            cutoff
+        )
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|row| u32::try_from(row.TaskID).map_err(|e| BeavorError::InvalidRow {
+                table:  "tasks",
+                id:     None,
+                column: "TaskID",
+                reason: e.to_string(),
+            }))
+            .collect::<Result<Vec<u32>>>()?;
+
+        // Note that hyperlinks are ON DELETE CASCADE, so do not need to be deleted manually
+        let purged = sqlx::query!("
+            DELETE
+            FROM tasks
+            WHERE DeletedAt IS NOT NULL AND DeletedAt < ?
+        ",
+            cutoff
+        )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        Self::forget_tasks(&mut tx, &ids).await?;
+
+        self.commit(tx).await?;
+
+        Ok(purged)
+    }
+
+    /// Reverses the most recent edit that has not been undone, and returns the edit that was made
+    /// to do so, or `None` if there is nothing to undo.
+    /// The undo log is kept in the database, so this works across restarts.
+    /// # Errors
+    /// Returns a `NotFound` error if the edit can no longer be undone, e.g., because the task
+    /// has been purged from the trash. The edit is then dropped from the log.
+    /// Also returns an error if any query fails, or if the log contains an invalid edit.
+    pub async fn undo(&self) -> Result<Option<Edit>> {
+        self.step(Stack::Undo).await
+    }
+
+    /// Makes the most recently undone edit again, and returns it, or `None` if there is nothing
+    /// to redo. Making any other edit clears what can be redone.
+    /// # Errors
+    /// As for `undo`.
+    pub async fn redo(&self) -> Result<Option<Edit>> {
+        self.step(Stack::Redo).await
+    }
+
+    /// Applies the top edit of `from` (inverted, when undoing), and moves it to the other stack
+    async fn step(&self, from: Stack) -> Result<Option<Edit>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(edit) = Self::pop_edit(&mut tx, from).await? else {
+            return Ok(None);
+        };
+
+        let (applied, to) = match from {
+            Stack::Undo => (edit.clone().inverse(), Stack::Redo),
+            Stack::Redo => (edit.clone(), Stack::Undo),
+        };
+
+        // Drop edits that can never be applied rather than leaving them stuck on top of the stack
+        match Self::apply_edit(&mut tx, &applied).await {
+            Ok(()) => Self::push_edit(&mut tx, to, &edit).await?,
+            Err(BeavorError::NotFound(what)) => {
+                self.commit(tx).await?;
+                return Err(BeavorError::NotFound(format!("Cannot {from}: {what}")));
+            },
+            Err(e) => return Err(e),
+        }
+
+        self.commit(tx).await?;
+
+        Ok(Some(applied))
+    }
+
+    /// Brings the database from the `before` side of the edit to its `after` side.
+    /// Tasks are never inserted here: a task that is created by undoing its deletion is still in
+    /// the trash, so it is restored instead.
+    async fn apply_edit(conn: &mut SqliteConnection, edit: &Edit) -> Result<()> {
+        match edit {
+            Edit::Task { before: None, after: Some(task) } => {
+                let id = task.id.ok_or_else(|| BeavorError::NotFound(format!("Task '{}' has no id", task.name)))?;
+                Self::untrash_task(conn, id).await?;
+            },
+            Edit::Task { before: Some(task), after: None } => {
+                Self::trash_task(conn, task).await?;
+            },
+            Edit::Task { before: Some(_), after: Some(task) } => {
+                // Undoing puts back the task as it was, whatever has happened to it since
+                Self::write_task(conn, task, false).await?;
+
+                // write_task dates a finished task today, so put back the date it was finished
+                let finished_date = task.finished_date.map(|d| d.to_string());
+                sqlx::query!("
+                    UPDATE tasks
+                    SET FinishedDate = ?
+                    WHERE TaskID == ?
+                ",
+                    finished_date,
+                    task.id
+                )
+                    .execute(&mut *conn)
+                    .await?;
+            },
+            Edit::Task { before: None, after: None } => (),
+            Edit::VacationDay { date, added } => {
+                Self::set_vacation_day(conn, date, *added).await?;
+            },
+            Edit::DaysOff { days, added: true } => {
+                for day in days {
+                    Self::insert_day_off(conn, day).await?;
+                }
+            },
+            Edit::DaysOff { days, added: false } => {
+                for day in days {
+                    Self::remove_day_off(conn, day.date).await?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Pushes a new edit onto the undo stack, forgetting what could be redone and the oldest edits
+    /// beyond `UNDO_LIMIT`.
+    /// This does not commit, so it should be called within the transaction that makes the edit.
+    async fn record_edit(conn: &mut SqliteConnection, edit: &Edit) -> Result<()> {
+        sqlx::query!("
+            DELETE
+            FROM undo_log
+            WHERE Stack == 'redo'
+        ")
+            .execute(&mut *conn)
+            .await?;
+
+        Self::push_edit(conn, Stack::Undo, edit).await?;
+
+        sqlx::query!("
+            DELETE
+            FROM undo_log
+            WHERE Stack == 'undo' AND EditID NOT IN (
+                SELECT EditID
+                FROM undo_log
+                WHERE Stack == 'undo'
+                ORDER BY EditID DESC
+                LIMIT ?
+            )
+        ",
+            UNDO_LIMIT
+        )
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn push_edit(conn: &mut SqliteConnection, stack: Stack, edit: &Edit) -> Result<()> {
+        let stack = stack.to_string();
+        let json = edit.to_json();
+
+        sqlx::query!("
+            INSERT INTO undo_log
+                (
+                    Stack,
+                    Edit
+                )
+            VALUES
+                (
+                    ?,
+                    ?
+                )
+        ",
+            stack,
+            json
+        )
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes and returns the most recent edit on the stack
+    async fn pop_edit(conn: &mut SqliteConnection, stack: Stack) -> Result<Option<Edit>> {
+        let stack = stack.to_string();
+
+        let Some(top) = sqlx::query!("
+            SELECT EditID, Edit
+            FROM undo_log
+            WHERE Stack == ?
+            ORDER BY EditID DESC
+            LIMIT 1
+        ",
+            stack
+        )
+            .fetch_optional(&mut *conn)
+            .await? else {
+                return Ok(None);
+            };
+
+        sqlx::query!("
+            DELETE
+            FROM undo_log
+            WHERE EditID == ?
+        ",
+            top.EditID
+        )
+            .execute(&mut *conn)
+            .await?;
+
+        Edit::from_json(&top.Edit).map(Some)
+    }
+
+    /// Returns every open task, soonest due first
+    /// # Errors
+    /// Returns an error if any database query fails, or if the database contains invalid tasks or invalid links.
+    pub async fn open_tasks(&self) -> Result<Vec<Task>> {
+        self.open_tasks_due(DueDate::Asap, DueDate::Never).await
+    }
+
+    /// Returns the open tasks due between `from` and `to` inclusive, soonest due first.
+    /// E.g., `open_tasks_due(DueDate::Asap, DueDate::Date(today))` is everything due by today.
+    /// # Errors
+    /// Returns an error if any database query fails, or if the database contains invalid tasks or invalid links.
+    pub async fn open_tasks_due(&self, from: DueDate, to: DueDate) -> Result<Vec<Task>> {
+        let mut query = QueryBuilder::<Sqlite>::new("
+            SELECT *
+            FROM tasks
+            WHERE Finished == false AND DeletedAt IS NULL
+        ");
+
+        // Dates are stored as %F strings, so they compare correctly as text
+        match from {
+            DueDate::Asap => (),
+            DueDate::Date(date) => {
+                query.push(" AND (DueKind > ").push_bind(DUE_DATE)
+                    .push(" OR (DueKind == ").push_bind(DUE_DATE)
+                    .push(" AND DueOn >= ").push_bind(date.to_string())
+                    .push("))");
+            },
+            DueDate::Never => {
+                query.push(" AND DueKind == ").push_bind(DUE_NEVER);
+            },
+        }
+        match to {
+            DueDate::Asap => {
+                query.push(" AND DueKind == ").push_bind(DUE_ASAP);
+            },
+            DueDate::Date(date) => {
+                query.push(" AND (DueKind < ").push_bind(DUE_DATE)
+                    .push(" OR (DueKind == ").push_bind(DUE_DATE)
+                    .push(" AND DueOn <= ").push_bind(date.to_string())
+                    .push("))");
+            },
+            DueDate::Never => (),
+        }
+
+        query.push(" ORDER BY DueKind, DueOn, TaskID");
+
+        let mut tasks: Vec<Task> = query.build()
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(Task::try_from)
+            .collect::<Result<_>>()?;
+
+        self.load_links(&mut tasks).await?;
+
+        Ok(tasks)
+    }
+
+    /// Returns one page of finished tasks matching `filter`, most recently finished first.
+    /// Tasks with no recorded `FinishedDate` come last, and never match a date range.
+    /// # Errors
+    /// Returns an error if any database query fails, or if the database contains invalid tasks or invalid links.
+    pub async fn finished_tasks(&self, filter: &ArchiveFilter) -> Result<Vec<Task>> {
+        let mut query = QueryBuilder::<Sqlite>::new("
+            SELECT *
+            FROM tasks
+            WHERE Finished == true AND DeletedAt IS NULL
+        ");
+
+        // Dates are stored as %F strings, so they compare correctly as text
+        if let Some(from) = filter.from {
+            query.push(" AND FinishedDate >= ").push_bind(from.to_string());
+        }
+        if let Some(to) = filter.to {
+            query.push(" AND FinishedDate <= ").push_bind(to.to_string());
+        }
+        if let Some(category) = &filter.category {
+            query.push(" AND Category == ").push_bind(category);
+        }
+        if let Some(text) = &filter.text {
+            let pattern = like_pattern(text);
+            query.push(" AND (Name LIKE ").push_bind(pattern.clone())
+                .push(" ESCAPE '\\' OR Notes LIKE ").push_bind(pattern)
+                .push(" ESCAPE '\\')");
+        }
+
+        // Widened so that a page past the end is empty rather than overflowing
+        let offset = i64::from(filter.page).saturating_mul(i64::from(filter.page_size));
+        query.push(" ORDER BY FinishedDate IS NULL, FinishedDate DESC, TaskID DESC LIMIT ").push_bind(filter.page_size)
+            .push(" OFFSET ").push_bind(offset);
+
+        let mut tasks: Vec<Task> = query.build()
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(Task::try_from)
+            .collect::<Result<_>>()?;
+
+        self.load_links(&mut tasks).await?;
+
+        Ok(tasks)
+    }
+
+    /// Searches the names, notes, categories and link text of open and finished tasks, best
+    /// matches first. Tasks in the trash are not searched.
+    /// Each word matches any word it is a prefix of, and text in double quotes matches as a phrase.
+    /// All words and phrases must match. A query with no words returns nothing.
+    /// # Errors
+    /// Returns an error if any database query fails, or if the database contains invalid tasks or invalid links.
+    pub async fn search(&self, query: &str) -> Result<Vec<Task>> {
+        let Some(expression) = match_expression(query) else {
+            return Ok(Vec::new());
+        };
+
+        let mut tasks: Vec<Task> = sqlx::query("
+            SELECT tasks.*
+            FROM task_search
+            JOIN tasks ON tasks.TaskID == task_search.rowid
+            WHERE task_search MATCH ? AND tasks.DeletedAt IS NULL
+            ORDER BY rank
+        ")
+            .bind(expression)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(Task::try_from)
+            .collect::<Result<_>>()?;
+
+        self.load_links(&mut tasks).await?;
+
+        Ok(tasks)
+    }
+
+    /// Fills in the links of each task from the hyperlinks table.
+    /// This is a single query however many tasks there are.
+    async fn load_links(&self, tasks: &mut [Task]) -> Result<()> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+
+        let ids = serde_json::to_string(&tasks.iter().filter_map(|t| t.id).collect::<Vec<_>>())
+            .expect("A list of integers always serializes");
+
+        let mut links: HashMap<u32, Vec<Hyperlink>> = HashMap::new();
+        for row in sqlx::query("
+            SELECT *, rowid
+            FROM hyperlinks
+            WHERE Task IN (SELECT value FROM json_each(?))
+            ORDER BY rowid
+        ")
+            .bind(ids)
+            .fetch_all(&self.pool)
+            .await?
+        {
+            let task_id: u32 = row.try_get("Task")?;
+            links.entry(task_id).or_default().push(Hyperlink::try_from(row)?);
+        }
+
+        for task in tasks {
+            task.links = task.id
+                .and_then(|id| links.remove(&id))
+                .unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if any database query fails, or if any row has a non-string category.
+    #[allow(non_snake_case)]
+    pub async fn categories(&self) -> Result<Vec<String>> {
+        sqlx::query!("
+            SELECT DISTINCT Category
+            FROM tasks
+            WHERE DeletedAt IS NULL
+            ORDER BY Category
+        ")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|r| r.Category.ok_or_else(|| BeavorError::invalid_task(None, "Category", "category is NULL")))
+            .collect()
+    }
+
+    /// Fetches the holidays in each of `years` from `provider`, unless they have already been
+    /// fetched from it and have not changed since, e.g., for `Schedule::horizon`. Holidays in those years from any other
+    /// source are replaced. Returns whether any were fetched.
+    /// # Errors
+    /// Returns the first error from the provider, after trying every year, or an error if any
+    /// database query fails. The holidays of a year that could not be fetched are kept as they
+    /// were, while those of other years are still updated.
+    pub async fn try_update_holidays(&self, provider: &dyn HolidayProvider, years: RangeInclusive<i32>) -> Result<bool> {
+        let source = provider.source();
+        let mut fetched_any = false;
+        let mut first_error = None;
+
+        for year in years {
+            let fetched_at = sqlx::query!("
+                SELECT FetchedAt
+                FROM holiday_years
+                WHERE Year == ? AND Source == ?
+            ",
+                year,
+                source
+            )
+                .fetch_optional(&self.pool)
+                .await?;
+
+            // A time that can't be read is fixed by fetching again
+            let up_to_date = match fetched_at.map(|record| parse_datetime(&record.FetchedAt)) {
+                Some(Ok(fetched_at)) => !provider.changed_since(fetched_at),
+                Some(Err(_)) | None => false,
+            };
+            if up_to_date {
+                continue;
+            }
+
+            match provider.holidays(year).await {
+                Ok(dates) => {
+                    self.store_holidays(year, &source, &dates).await?;
+                    fetched_any = true;
+                },
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                },
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(fetched_any),
+        }
+    }
+
+    /// Returns where the holidays stored for `year` came from and when, or `None` if none have
+    /// been fetched for it
+    /// # Errors
+    /// Returns an error if the query fails, or the time fetched cannot be read.
+    #[allow(non_snake_case)]
+    pub async fn holidays_fetched(&self, year: i32) -> Result<Option<FetchedHolidays>> {
+        let Some(record) = sqlx::query!("
+            SELECT Source, FetchedAt
+            FROM holiday_years
+            WHERE Year == ?
+            ORDER BY FetchedAt DESC
+        ",
+            year
+        )
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(FetchedHolidays {
+            year,
+            fetched_at: parse_datetime(&record.FetchedAt).map_err(|e| BeavorError::InvalidRow {
+                table:  "holiday_years",
+                id:     None,
+                column: "FetchedAt",
+                reason: e.to_string(),
+            })?,
+            source: record.Source,
+        }))
+    }
+
+    /// Replaces the holidays in `year` with `dates`, and records that they came from `source`
+    async fn store_holidays(&self, year: i32, source: &str, dates: &[NaiveDate]) -> Result<()> {
+        let first_day = format!("{year:04}-01-01");
+        let last_day = format!("{year:04}-12-31");
+        let fetched_at = format_datetime(now_datetime());
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("
+            DELETE
+            FROM days_off
+            WHERE Reason == 'stat_holiday' AND Day BETWEEN ? AND ?
+        ",
+            first_day,
+            last_day
+        )
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!("
+            DELETE
+            FROM holiday_years
+            WHERE Year == ?
+        ",
+            year
+        )
+            .execute(&mut *tx)
+            .await?;
+
+        if !dates.is_empty() {
+            // A vacation day already booked on a holiday stays a vacation day
+            QueryBuilder::<Sqlite>::new("INSERT OR IGNORE INTO days_off (Day, Reason, Source) ")
+                .push_values(dates, |mut row, date| {
+                    row.push_bind(date.to_string())
+                        .push_bind("stat_holiday")
+                        .push_bind(source);
+                })
+                .build()
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query!("
+            INSERT INTO holiday_years (Year, Source, FetchedAt)
+            VALUES (?, ?, ?)
+        ",
+            year,
+            source,
+            fetched_at
+        )
+            .execute(&mut *tx)
+            .await?;
+
+        self.commit(tx).await?;
+
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the query fails, e.g., if the date is already a day off.
+    pub async fn add_vacation_day(&self, date: &NaiveDate) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        Self::set_vacation_day(&mut tx, date, true).await?;
+        Self::record_edit(&mut tx, &Edit::VacationDay { date: *date, added: true }).await?;
+
+        self.commit(tx).await?;
+
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn delete_vacation_day(&self, date: &NaiveDate) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // Deleting a day that wasn't a vacation day is not worth undoing
+        if Self::set_vacation_day(&mut tx, date, false).await? {
+            Self::record_edit(&mut tx, &Edit::VacationDay { date: *date, added: false }).await?;
+        }
+
+        self.commit(tx).await?;
+
+        Ok(())
+    }
+
+    /// Adds or deletes a vacation day, and returns whether anything changed.
+    /// This does not commit, so it should be called within a transaction.
+    async fn set_vacation_day(conn: &mut SqliteConnection, date: &NaiveDate, is_vacation: bool) -> Result<bool> {
+        let date_string = date.to_string();
+
+        let result = if is_vacation {
+            sqlx::query!("
+                INSERT INTO days_off
+                    (
+                        Day,
+                        Reason
+                    )
+                VALUES
+                    (
+                        ?,
+                        'vacation'
+                    )
+                ",
+                date_string
+            )
+                .execute(&mut *conn)
+                .await?
+        } else {
+            sqlx::query!("
+                DELETE
+                FROM days_off
+                WHERE Day == ? AND Reason=='vacation'
+            ",
+                date_string
+            )
+                .execute(&mut *conn)
+                .await?
+        };
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Adds days off, e.g., every day of a trip from `DayOff::range`, as one edit that can be
+    /// undone. Dates that are already off are left as they are.
+    /// # Errors
+    /// Returns an error if any query fails, e.g., if a day off is a holiday.
+    pub async fn add_days_off(&self, days: &[DayOff]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut added = Vec::new();
+        for day in days {
+            if Self::insert_day_off(&mut tx, day).await? {
+                added.push(day.clone());
+            }
+        }
+
+        if !added.is_empty() {
+            Self::record_edit(&mut tx, &Edit::DaysOff { days: added, added: true }).await?;
+        }
+
+        self.commit(tx).await?;
+
+        Ok(())
+    }
+
+    /// Deletes the vacation and travel days on `dates`, as one edit that can be undone. Holidays
+    /// are left alone, since they are replaced whenever they are fetched.
+    /// # Errors
+    /// Returns an error if any query fails.
+    pub async fn delete_days_off(&self, dates: &[NaiveDate]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut deleted = Vec::new();
+        for date in dates {
+            if let Some(day) = Self::remove_day_off(&mut tx, *date).await? {
+                deleted.push(day);
+            }
+        }
+
+        if !deleted.is_empty() {
+            Self::record_edit(&mut tx, &Edit::DaysOff { days: deleted, added: false }).await?;
+        }
+
+        self.commit(tx).await?;
+
+        Ok(())
+    }
+
+    /// Adds a day off unless its date is already off, and returns whether it was added.
+    /// This does not commit, so it should be called within a transaction.
+    async fn insert_day_off(conn: &mut SqliteConnection, day: &DayOff) -> Result<bool> {
+        if day.reason == Reason::StatHoliday {
+            return Err(BeavorError::ConstraintViolation("holidays can only be stored by fetching them".into()));
+        }
+
+        let date = day.date.to_string();
+        let reason = day.reason.as_str();
+        let start = day.hours.map(|h| h.start().format(TIME_OFF_FORMAT).to_string());
+        let end = day.hours.map(|h| h.end().format(TIME_OFF_FORMAT).to_string());
+
+        let result = sqlx::query!("
+            INSERT OR IGNORE INTO days_off (Day, Reason, Label, StartOff, EndOff)
+            VALUES (?, ?, ?, ?, ?)
+        ",
+            date,
+            reason,
+            day.label,
+            start,
+            end
+        )
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes the vacation or travel day on a date, and returns it if there was one.
+    /// This does not commit, so it should be called within a transaction.
+    #[allow(non_snake_case)]
+    async fn remove_day_off(conn: &mut SqliteConnection, date: NaiveDate) -> Result<Option<DayOff>> {
+        let date = date.to_string();
+
+        sqlx::query!("
+            DELETE
+            FROM days_off
+            WHERE Day == ? AND Reason != 'stat_holiday'
+            RETURNING Day, Reason, Label, StartOff, EndOff
+        ",
+            date
+        )
+            .fetch_optional(&mut *conn)
+            .await?
+            .map(|record| parse_day_off_row(record.Day, record.Reason, record.Label, record.StartOff, record.EndOff))
+            .transpose()
+    }
+
+    /// # Errors
+    /// Returns an error if the query fails, or `days_off` contains invalid dates.
+    #[allow(non_snake_case)]
+    pub async fn vacation_days(&self) -> Result<Vec<NaiveDate>> {
+        sqlx::query!("
+            SELECT Day
+            FROM days_off
+            WHERE Reason == 'vacation'
+            ORDER BY Day
+        ")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|record| parse_day_off(record.Day))
+            .collect()
+    }
+
+    /// # Errors
+    /// Returns an error if the query fails, or `days_off` contains invalid dates.
+    #[allow(non_snake_case)]
+    pub async fn holidays(&self) -> Result<Vec<NaiveDate>> {
+        sqlx::query!("
+            SELECT Day
+            FROM days_off
+            WHERE Reason == 'stat_holiday'
+            ORDER BY Day
+        ")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|record| parse_day_off(record.Day))
+            .collect()
+    }
+
+    /// Returns every day off stored, whether holiday, vacation or travel, in order. This never
+    /// fetches holidays, so it works offline; keep them up to date with `try_update_holidays`.
+    /// # Errors
+    /// Returns an error if the query fails, or `days_off` contains invalid rows.
+    #[allow(non_snake_case)]
+    pub async fn days_off(&self) -> Result<Vec<DayOff>> {
+        sqlx::query!("
+            SELECT Day, Reason, Label, StartOff, EndOff
+            FROM days_off
+            ORDER BY Day
+        ")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|record| parse_day_off_row(record.Day, record.Reason, record.Label, record.StartOff, record.EndOff))
+            .collect()
+    }
+
+    /// Spreads the work on `tasks` over the days they can be worked on with `strategy`
+    ///
+    /// # Errors
+    /// Returns an error if the days off cannot be loaded.
+    pub async fn schedule (&self, work_week: WorkWeek, tasks: &[Task], strategy: &dyn SchedulingStrategy) -> Result<Schedule> {
+        Ok(Schedule::with_strategy(
+            self.days_off().await?,
+            tasks,
+            work_week,
+            strategy,
+        ))
+    }
+}
+
+/// Pure
+///
+/// Parses the `Day` column of `days_off`
+fn parse_day_off(day: Option<String>) -> Result<NaiveDate> {
+    let invalid = |reason: String| BeavorError::InvalidRow {
+        table:  "days_off",
+        id:     None,
+        column: "Day",
+        reason,
+    };
+
+    day.ok_or_else(|| invalid("day is NULL".into()))?
+        .parse::<NaiveDate>()
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// How the hours of a partial day off are stored
+const TIME_OFF_FORMAT: &str = "%H:%M:%S";
+
+/// Pure
+///
+/// Parses a row of `days_off`
+fn parse_day_off_row(day: Option<String>, reason: Option<String>, label: String, start: Option<String>, end: Option<String>) -> Result<DayOff> {
+    let invalid = |column: &'static str, reason: String| BeavorError::InvalidRow {
+        table: "days_off",
+        id:    None,
+        column,
+        reason,
+    };
+    let parse_time = |column: &'static str, time: &str| NaiveTime::parse_from_str(time, TIME_OFF_FORMAT)
+        .map_err(|e| invalid(column, e.to_string()));
+
+    let hours = match (start, end) {
+        (Some(start), Some(end)) => Some(
+            HourRange::new(parse_time("StartOff", &start)?, parse_time("EndOff", &end)?)
+                .ok_or_else(|| invalid("EndOff", format!("{end} is not after {start}")))?
+        ),
+        (None, None) => None,
+        _ => return Err(invalid("EndOff", "only one end of the hours off is set".into())),
+    };
+
+    Ok(DayOff {
+        date: parse_day_off(day)?,
+        reason: reason
+            .ok_or_else(|| invalid("Reason", "reason is NULL".into()))?
+            .parse()
+            .map_err(|e| invalid("Reason", e))?,
+        label,
+        hours,
+    })
+}
+
+/// Pure
+///
+/// Turns a search typed by the user into an FTS5 match expression. Quoted text becomes a phrase
+/// and every other word becomes a prefix query. Everything is quoted, so the user cannot write an
+/// invalid expression. Returns `None` if there is nothing to search for.
+fn match_expression(query: &str) -> Option<String> {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+
+    let terms: Vec<String> = query
+        .split('"')
+        .enumerate()
+        .flat_map(|(i, part)| if i % 2 == 1 {
+            // Inside quotes
+            let phrase = part.split_whitespace().collect::<Vec<_>>().join(" ");
+            (!phrase.is_empty()).then(|| quote(&phrase)).into_iter().collect::<Vec<_>>()
+        } else {
+            part.split_whitespace().map(|word| quote(word) + "*").collect()
+        })
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Pure
+///
+/// Turns text typed by the user into a LIKE pattern matching anything that contains it. `%` and
+/// `_` match themselves rather than any text, so the pattern must be used with `ESCAPE '\'`.
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{
+        Datelike,
+        Weekday,
+    };
+
+    use crate::schedule::{
+        DateIterator,
+        Strategy,
+    };
+    use crate::fixtures::{
+        Fixture,
+        FixedHolidays,
+        FailingHolidays,
+        link,
+        task_with_links,
+        urls,
+    };
+
+    /// Makes any write of a hyperlink with the url "fail" abort, to simulate a failure part-way
+    /// through writing a task
+    async fn inject_link_failure(db: &Connection) {
+        sqlx::query("
+            CREATE TRIGGER inject_failure
+            BEFORE INSERT ON hyperlinks
+            WHEN NEW.Url == 'fail'
+            BEGIN
+                SELECT RAISE(ABORT, 'injected failure');
+            END
+        ")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_task_writes_links() {
+        let db = Connection::in_memory().await.unwrap();
+
+        db.create_task(&task_with_links("Task", &["a", "b", "c"])).await.unwrap();
+
+        let tasks = db.open_tasks().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(urls(&tasks[0]), vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_create_task_writes_nothing() {
+        let db = Connection::in_memory().await.unwrap();
+        inject_link_failure(&db).await;
+
+        assert!(db.create_task(&task_with_links("Task", &["a", "fail"])).await.is_err());
+
+        assert!(db.open_tasks().await.unwrap().is_empty());
+        let links: i64 = sqlx::query("SELECT COUNT(*) FROM hyperlinks")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(links, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_update_task_changes_nothing() {
+        let db = Connection::in_memory().await.unwrap();
+        let original = db.create_task(&task_with_links("Original", &["a", "b"])).await.unwrap();
+        inject_link_failure(&db).await;
+
+        let mut edited = original.clone();
+        edited.name = "Edited".into();
+        edited.links = vec![link("c"), link("fail")];
+        assert!(db.update_task(&edited).await.is_err());
+
+        let tasks = db.open_tasks().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name, "Original");
+        assert_eq!(urls(&tasks[0]), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_delete_task_changes_nothing() {
+        let db = Connection::in_memory().await.unwrap();
+        let task = db.create_task(&task_with_links("Task", &["a", "b"])).await.unwrap();
+
+        sqlx::query("
+            CREATE TRIGGER inject_failure
+            BEFORE INSERT ON task_history
+            WHEN NEW.Action == 'delete'
+            BEGIN
+                SELECT RAISE(ABORT, 'injected failure');
+            END
+        ")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        assert!(db.delete_task(task).await.is_err());
+
+        let tasks = db.open_tasks().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(urls(&tasks[0]), vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_filters_and_pages() {
+        let db = Connection::in_memory().await.unwrap();
+
+        for (name, category, finished, day) in [
+            ("Open report",     "Work", false, 1),
+            ("Old report",      "Work", true,  1),
+            ("New report",      "Work", true,  10),
+            ("Groceries",       "Home", true,  5),
+            ("Report taxes",    "Home", true,  20),
+        ] {
+            db.create_task(&Task {
+                name: name.into(),
+                category: category.into(),
+                finished,
+                finished_date: NaiveDate::from_ymd_opt(2024, 1, day),
+                ..Task::default()
+            }).await.unwrap();
+        }
+
+        let names = |tasks: Vec<Task>| tasks.into_iter().map(|t| t.name).collect::<Vec<_>>();
+
+        assert_eq!(
+            names(db.finished_tasks(&ArchiveFilter::default()).await.unwrap()),
+            vec!["Report taxes", "New report", "Groceries", "Old report"]
+        );
+
+        assert_eq!(
+            names(db.finished_tasks(&ArchiveFilter {
+                text: Some("report".into()),
+                category: Some("Work".into()),
+                ..ArchiveFilter::default()
+            }).await.unwrap()),
+            vec!["New report", "Old report"]
+        );
+
+        assert_eq!(
+            names(db.finished_tasks(&ArchiveFilter {
+                from: NaiveDate::from_ymd_opt(2024, 1, 2),
+                to: NaiveDate::from_ymd_opt(2024, 1, 10),
+                ..ArchiveFilter::default()
+            }).await.unwrap()),
+            vec!["New report", "Groceries"]
+        );
+
+        assert_eq!(
+            names(db.finished_tasks(&ArchiveFilter {
+                page: 1,
+                page_size: 3,
+                ..ArchiveFilter::default()
+            }).await.unwrap()),
+            vec!["Old report"]
+        );
+
+        // A page past the end is empty, even if it would overflow the offset
+        assert!(db.finished_tasks(&ArchiveFilter {
+            page: u32::MAX,
+            page_size: u32::MAX,
+            ..ArchiveFilter::default()
+        }).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_text_is_not_a_pattern() {
+        let db = Connection::in_memory().await.unwrap();
+        for name in ["Raise rent 50%", "Raise rent 500", "file_name", "filename", "C:\\temp"] {
+            db.create_task(&Task {
+                name: name.into(),
+                finished: true,
+                ..Task::default()
+            }).await.unwrap();
+        }
+
+        let names = |text: &str| {
+            let filter = ArchiveFilter {
+                text: Some(text.into()),
+                ..ArchiveFilter::default()
+            };
+            let db = db.clone();
+            async move {
+                db.finished_tasks(&filter).await.unwrap().into_iter().map(|t| t.name).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(names("50%").await, vec!["Raise rent 50%"]);
+        assert_eq!(names("file_").await, vec!["file_name"]);
+        assert_eq!(names("\\temp").await, vec!["C:\\temp"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_task_records_finished_date() {
+        let db = Connection::in_memory().await.unwrap();
+        let mut task = db.create_task(&task_with_links("Task", &[])).await.unwrap();
+        assert_eq!(task.finished_date, None);
+
+        let finished_date = |db: Connection| async move {
+            db.finished_tasks(&ArchiveFilter::default()).await.unwrap()
+                .first()
+                .and_then(|t| t.finished_date)
+        };
+
+        task.finished = true;
+        task = db.update_task(&task).await.unwrap();
+        assert_eq!(finished_date(db.clone()).await, Some(today_date()));
+
+        // Saving again while finished keeps the original date, even if the draft says otherwise
+        task.finished_date = NaiveDate::from_ymd_opt(1971, 1, 1);
+        task = db.update_task(&task).await.unwrap();
+        assert_eq!(finished_date(db.clone()).await, Some(today_date()));
+
+        task.finished = false;
+        db.update_task(&task).await.unwrap();
+        let reopened = db.open_tasks().await.unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened[0].finished_date, None);
+    }
+
+    #[tokio::test]
+    async fn test_update_task_refuses_outdated_copy() {
+        let (db, tasks) = Fixture::new()
+            .task_with_links("Task", &[])
+            .build()
+            .await;
+
+        let saved = db.update_task(&Task { name: "Theirs".into(), ..tasks[0].clone() }).await.unwrap();
+        assert_eq!(saved.revision, tasks[0].revision + 1);
+
+        match db.update_task(&Task { name: "Mine".into(), ..tasks[0].clone() }).await {
+            Err(BeavorError::Conflict(stored)) => assert_eq!(*stored, saved),
+            other => panic!("Expected a conflict, got {other:?}"),
+        }
+        assert_eq!(db.open_tasks().await.unwrap(), vec![saved.clone()]);
+
+        // Undoing doesn't care about revisions
+        db.undo().await.unwrap();
+        assert_eq!(db.open_tasks().await.unwrap()[0].name, "Task");
+
+        // A trashed task is missing rather than conflicting
+        db.delete_task(saved.clone()).await.unwrap();
+        assert!(matches!(db.update_task(&saved).await, Err(BeavorError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_task_history_records_each_write() {
+        let db = Connection::in_memory().await.unwrap();
+
+        let mut task = db.create_task(&task_with_links("Task", &["a"])).await.unwrap();
+        let id = task.id.unwrap();
+
+        task.due_date = DueDate::Never;
+        task.time_needed = Duration::minutes(30);
+        task = db.update_task(&task).await.unwrap();
+
+        // Saving without changes records nothing
+        task = db.update_task(&task).await.unwrap();
+
+        db.delete_task(task).await.unwrap();
+
+        let history = db.task_history(id).await.unwrap();
+        let of = |action: ChangeAction| history.iter().filter(|c| c.action == action).collect::<Vec<_>>();
+
+        assert!(!of(ChangeAction::Create).is_empty());
+        assert!(of(ChangeAction::Create).iter().all(|c| c.old_value.is_none()));
+
+        let updates = of(ChangeAction::Update);
+        assert_eq!(
+            updates.iter().map(|c| (c.field.as_str(), c.old_value.as_deref(), c.new_value.as_deref())).collect::<Vec<_>>(),
+            vec![
+                ("Time",    Some("0"),    Some("1800")),
+                ("DueDate", Some("ASAP"), Some("None")),
+            ]
+        );
+
+        assert!(!of(ChangeAction::Delete).is_empty());
+        assert!(of(ChangeAction::Delete).iter().all(|c| c.new_value.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_task_can_be_restored() {
+        let db = Connection::in_memory().await.unwrap();
+        let task = db.create_task(&task_with_links("Task", &["a"])).await.unwrap();
+        let id = task.id.unwrap();
+
+        db.delete_task(task.clone()).await.unwrap();
+
+        assert!(db.open_tasks().await.unwrap().is_empty());
+        let trash = db.trashed_tasks().await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].task, task);
+
+        // Trashed tasks cannot be edited
+        assert!(matches!(db.update_task(&task).await, Err(BeavorError::NotFound(_))));
+
+        assert_eq!(db.restore_task(id).await.unwrap(), task);
+        assert_eq!(db.open_tasks().await.unwrap(), vec![task]);
+        assert!(db.trashed_tasks().await.unwrap().is_empty());
+        assert!(matches!(db.restore_task(id).await, Err(BeavorError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_purge_trash_respects_retention() {
+        let db = Connection::in_memory().await.unwrap();
+        let task = db.create_task(&task_with_links("Task", &["a"])).await.unwrap();
+        db.delete_task(task).await.unwrap();
+
+        assert_eq!(db.purge_trash(Duration::days(1)).await.unwrap(), 0);
+        assert_eq!(db.trashed_tasks().await.unwrap().len(), 1);
+
+        assert_eq!(db.purge_trash(Duration::days(-1)).await.unwrap(), 1);
+        assert!(db.trashed_tasks().await.unwrap().is_empty());
+
+        let links: i64 = sqlx::query("SELECT COUNT(*) FROM hyperlinks")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(links, 0);
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_update() {
+        let db = Connection::in_memory().await.unwrap();
+        let task = db.create_task(&task_with_links("Task", &["a"])).await.unwrap();
+        let edited = Task {
+            name: "Edited".into(),
+            links: vec![link("b")],
+            ..task.clone()
+        };
+        db.update_task(&edited).await.unwrap();
+
+        db.undo().await.unwrap().unwrap();
+        let open = db.open_tasks().await.unwrap();
+        assert_eq!(open[0].name, "Task");
+        assert_eq!(urls(&open[0]), vec!["a"]);
+
+        db.redo().await.unwrap().unwrap();
+        let open = db.open_tasks().await.unwrap();
+        assert_eq!(open[0].name, "Edited");
+        assert_eq!(urls(&open[0]), vec!["b"]);
+        assert!(db.redo().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_undo_create_and_delete() {
+        let db = Connection::in_memory().await.unwrap();
+        let task = db.create_task(&task_with_links("Task", &["a", "b"])).await.unwrap();
+        db.delete_task(task.clone()).await.unwrap();
+
+        // Undoing the delete brings back the task and its links
+        db.undo().await.unwrap().unwrap();
+        assert_eq!(db.open_tasks().await.unwrap(), vec![task.clone()]);
+
+        // Undoing the create puts it in the trash
+        db.undo().await.unwrap().unwrap();
+        assert!(db.open_tasks().await.unwrap().is_empty());
+        assert!(db.undo().await.unwrap().is_none());
+
+        db.redo().await.unwrap().unwrap();
+        assert_eq!(db.open_tasks().await.unwrap(), vec![task]);
+    }
+
+    #[tokio::test]
+    async fn test_undo_vacation_day() {
+        let db = Connection::in_memory().await.unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+        db.add_vacation_day(&date).await.unwrap();
+        db.undo().await.unwrap().unwrap();
+        assert!(db.vacation_days().await.unwrap().is_empty());
+
+        db.redo().await.unwrap().unwrap();
+        assert_eq!(db.vacation_days().await.unwrap(), vec![date]);
+    }
+
+    #[tokio::test]
+    async fn test_new_edit_clears_redo() {
+        let db = Connection::in_memory().await.unwrap();
+        db.create_task(&task_with_links("First", &[])).await.unwrap();
+        db.undo().await.unwrap().unwrap();
+
+        db.create_task(&task_with_links("Second", &[])).await.unwrap();
+        assert!(db.redo().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_undo_log_survives_reconnect() {
+        let path = std::env::temp_dir().join(format!("beavor_test_undo_log_survives_reconnect_{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let db = Connection::with_new_database(path).await.unwrap();
+        db.create_task(&task_with_links("Task", &[])).await.unwrap();
+        db.pool.close().await;
+
+        let db = Connection::new(path).await.unwrap();
+        db.undo().await.unwrap().unwrap();
+        assert!(db.open_tasks().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_undo_purged_task_is_dropped() {
+        let db = Connection::in_memory().await.unwrap();
+        let task = db.create_task(&task_with_links("Task", &[])).await.unwrap();
+        db.delete_task(task).await.unwrap();
+        db.purge_trash(Duration::days(-1)).await.unwrap();
+
+        // Both the create and the delete are gone with the task
+        assert!(db.undo().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_undo_after_purge_leaves_task_with_reused_id_alone() {
+        let db = Connection::in_memory().await.unwrap();
+        let purged = db.create_task(&task_with_links("Purged", &[])).await.unwrap();
+        let purged = db.update_task(&Task { name: "Renamed".into(), ..purged }).await.unwrap();
+        db.delete_task(purged.clone()).await.unwrap();
+        db.purge_trash(Duration::days(-1)).await.unwrap();
+
+        let task = db.create_task(&task_with_links("New", &[])).await.unwrap();
+        assert_eq!(task.id, purged.id);
+
+        // Undoing the create trashes the new task, and the purged task's edits are not undone onto it
+        db.undo().await.unwrap().unwrap();
+        assert!(db.undo().await.unwrap().is_none());
+        let trashed = db.trashed_tasks().await.unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].task.name, "New");
+
+        // Nor is the purged task's history shown as the new task's
+        let history: i64 = sqlx::query("SELECT COUNT(*) FROM task_history WHERE NewValue == 'Renamed'")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(history, 0);
+    }
+
+    #[test]
+    fn test_match_expression() {
+        assert_eq!(match_expression("  "), None);
+        assert_eq!(match_expression("rep"), Some("\"rep\"*".into()));
+        assert_eq!(match_expression("draft \"annual  report\""), Some("\"draft\"* \"annual report\"".into()));
+        // An unclosed quote still makes a phrase
+        assert_eq!(match_expression("\"annual report"), Some("\"annual report\"".into()));
+        assert_eq!(match_expression("AND OR("), Some("\"AND\"* \"OR(\"*".into()));
+    }
+
+    #[tokio::test]
+    async fn test_search_finds_prefixes_and_phrases() {
+        let db = Connection::in_memory().await.unwrap();
+        let report = db.create_task(&Task {
+            name: "Annual report".into(),
+            notes: "Send to the board".into(),
+            ..Task::default()
+        }).await.unwrap();
+        let finished = db.create_task(&Task {
+            name: "Budget".into(),
+            category: "Finance".into(),
+            finished: true,
+            links: vec![Hyperlink { url: "https://example.com".into(), display: "Spreadsheet".into(), id: 0 }],
+            ..Task::default()
+        }).await.unwrap();
+        let deleted = db.create_task(&Task {
+            name: "Report draft".into(),
+            ..Task::default()
+        }).await.unwrap();
+        db.delete_task(deleted).await.unwrap();
+
+        let ids = |tasks: Vec<Task>| tasks.into_iter().map(|t| t.id.unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(ids(db.search("rep").await.unwrap()), vec![report.id.unwrap()]);
+        assert_eq!(ids(db.search("\"the board\"").await.unwrap()), vec![report.id.unwrap()]);
+        assert!(db.search("\"board the\"").await.unwrap().is_empty());
+        assert_eq!(ids(db.search("financ spread").await.unwrap()), vec![finished.id.unwrap()]);
+
+        // The index follows edits to the task and its links
+        db.update_task(&Task {
+            name: "Forecast".into(),
+            links: Vec::new(),
+            ..finished.clone()
+        }).await.unwrap();
+        assert!(db.search("spread").await.unwrap().is_empty());
+        assert_eq!(ids(db.search("forecast").await.unwrap()), vec![finished.id.unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn test_search_follows_links_edited_in_place() {
+        let (db, tasks) = Fixture::new()
+            .task_with_links("A", &["https://a.example.com"])
+            .task_with_links("B", &["https://b.example.com"])
+            .build()
+            .await;
+        let (a, b) = (tasks[0].id.unwrap(), tasks[1].id.unwrap());
+        let ids = |tasks: Vec<Task>| tasks.into_iter().map(|t| t.id.unwrap()).collect::<Vec<_>>();
+
+        // Saving a task rewrites its links, so only other programs update them in place
+        sqlx::query("UPDATE hyperlinks SET Display = 'Minutes' WHERE Task == ?")
+            .bind(a)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(ids(db.search("minutes").await.unwrap()), vec![a]);
+        assert_eq!(ids(db.search("b.example").await.unwrap()), vec![b]);
+
+        // Moving a link to another task takes its text with it
+        sqlx::query("UPDATE hyperlinks SET Task = ? WHERE Task == ?")
+            .bind(b)
+            .bind(a)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(ids(db.search("minutes").await.unwrap()), vec![b]);
+        assert_eq!(ids(db.search("b.example").await.unwrap()), vec![b]);
+    }
+
+    #[tokio::test]
+    async fn test_open_tasks_loads_each_tasks_links() {
+        let (db, tasks) = Fixture::new()
+            .task_with_links("A", &["a1", "a2"])
+            .task_with_links("B", &[])
+            .task_with_links("C", &["c1"])
+            .build()
+            .await;
+
+        assert_eq!(db.open_tasks().await.unwrap(), tasks);
+    }
+
+    /// Run with `cargo test --release -p backend -- --ignored --nocapture benchmark`
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn benchmark_open_tasks_10000() {
+        let db = Connection::in_memory().await.unwrap();
+
+        sqlx::query("
+            WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 10000)
+            INSERT INTO tasks (Category, Finished, Name, Budget, Time, Used, NextAction, DueKind, DueOn, Notes, DateAdded)
+            SELECT 'Work', false, 'Task ' || i, 3600, 3600, 0, '2024-01-01', 1, '2024-02-01', 'Notes', '2024-01-01'
+            FROM n
+        ")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        for link in ["First", "Second"] {
+            sqlx::query("
+                INSERT INTO hyperlinks (Url, Display, Task)
+                SELECT 'https://example.com/' || TaskID, ?, TaskID
+                FROM tasks
+            ")
+                .bind(link)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        let start = std::time::Instant::now();
+        let tasks = db.open_tasks().await.unwrap();
+        let elapsed = start.elapsed();
+
+        println!("open_tasks loaded {} tasks in {elapsed:?}", tasks.len());
+        assert_eq!(tasks.len(), 10_000);
+        assert!(tasks.iter().all(|t| t.links.len() == 2));
+        assert!(elapsed < std::time::Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_times_round_trip_to_the_second() {
+        let db = Connection::in_memory().await.unwrap();
+        let mut task = db.create_task(&Task {
+            time_needed: Duration::minutes(90) + Duration::seconds(5),
+            time_used: Duration::seconds(59),
+            ..Task::default()
+        }).await.unwrap();
+
+        assert_eq!(task.time_needed, Duration::seconds(5405));
+        assert_eq!(task.time_used, Duration::seconds(59));
+
+        // Many short sessions add up
+        for _ in 0..3 {
+            task.time_used = task.time_used + Duration::seconds(20);
+            task = db.update_task(&task).await.unwrap();
+        }
+
+        assert_eq!(db.open_tasks().await.unwrap()[0].time_used, Duration::seconds(119));
+    }
+
+    #[tokio::test]
+    async fn test_open_tasks_sorted_and_filtered_by_due_date() {
+        let db = Connection::in_memory().await.unwrap();
+        let date = |day| DueDate::Date(NaiveDate::from_ymd_opt(2024, 1, day).unwrap());
+
+        for (name, due_date) in [("never", DueDate::Never), ("10th", date(10)), ("asap", DueDate::Asap), ("2nd", date(2)), ("20th", date(20))] {
+            db.create_task(&Task {
+                name: name.into(),
+                due_date,
+                ..Task::default()
+            }).await.unwrap();
+        }
+
+        let names = |tasks: Vec<Task>| tasks.into_iter().map(|t| t.name).collect::<Vec<_>>();
+
+        assert_eq!(names(db.open_tasks().await.unwrap()), vec!["asap", "2nd", "10th", "20th", "never"]);
+        assert_eq!(names(db.open_tasks_due(DueDate::Asap, date(10)).await.unwrap()), vec!["asap", "2nd", "10th"]);
+        assert_eq!(names(db.open_tasks_due(date(3), date(20)).await.unwrap()), vec!["10th", "20th"]);
+        assert_eq!(names(db.open_tasks_due(date(11), DueDate::Never).await.unwrap()), vec!["20th", "never"]);
+        assert_eq!(names(db.open_tasks_due(DueDate::Never, DueDate::Never).await.unwrap()), vec!["never"]);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_database_is_migrated() {
+        let db = Connection::in_memory().await.unwrap();
+        let mut conn = db.pool.acquire().await.unwrap();
+
+        assert_eq!(migrations::current_version(&mut conn).await.unwrap(), migrations::latest_version());
+        // The pool has a single connection, which must be given back before using the database
+        drop(conn);
+        assert!(db.open_tasks().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_new_opens_database_created_by_with_new_database() {
+        let path = std::env::temp_dir().join(format!("beavor_test_new_opens_database_{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let db = Connection::with_new_database(path).await.unwrap();
+        let task = db.create_task(&task_with_links("Task", &["a"])).await.unwrap();
+        db.pool.close().await;
+
+        let db = Connection::new(path).await.unwrap();
+        assert_eq!(db.open_tasks().await.unwrap(), vec![task]);
+    }
+
+    /// Returns the path of a database in an empty directory of its own, so that its backups
+    /// don't mix with those of other tests
+    fn database_in_temp_dir(test: &str) -> String {
+        let directory = std::env::temp_dir().join(format!("beavor_{test}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        directory.join("worklist.db").to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_restore_from_backup_replaces_damaged_database() {
+        let path = database_in_temp_dir("restore_from_backup");
+        let policy = BackupPolicy::for_database(&path);
+
+        let db = Connection::with_new_database(&path).await.unwrap();
+        let kept = db.create_task(&Task { name: "Kept".into(), ..Task::default() }).await.unwrap();
+        let backup = db.backup(&policy).await.unwrap();
+        db.create_task(&Task { name: "Lost".into(), ..Task::default() }).await.unwrap();
+        db.pool.close().await;
+
+        assert_eq!(policy.backups().unwrap(), vec![backup.clone()]);
+
+        fs::write(&path, "This is not a database").unwrap();
+        assert!(matches!(Connection::new(&path).await, Err(BeavorError::Corrupt(_))));
+
+        let db = Connection::restore_from(&backup.path, &path).await.unwrap();
+        assert_eq!(db.open_tasks().await.unwrap(), vec![kept]);
+
+        // The damaged database is kept aside
+        let damaged: Vec<_> = fs::read_dir(Path::new(&path).parent().unwrap()).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.starts_with("worklist.db.damaged-"))
+            .collect();
+        assert_eq!(damaged.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_from_rejects_damaged_backup() {
+        let path = database_in_temp_dir("restore_from_damaged_backup");
+        let backup = Path::new(&path).with_file_name("backup.db");
+        fs::write(&backup, "This is not a database").unwrap();
+
+        assert!(matches!(Connection::restore_from(&backup, &path).await, Err(BeavorError::Corrupt(_))));
+        assert!(!Path::new(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_latest_good_backup_skips_damaged_backups() {
+        let path = database_in_temp_dir("latest_good_backup");
+        let policy = BackupPolicy::for_database(&path);
+
+        assert_eq!(Connection::latest_good_backup(&policy).await.unwrap(), None);
+
+        let db = Connection::with_new_database(&path).await.unwrap();
+        let good = db.backup(&policy).await.unwrap();
+        db.pool.close().await;
+
+        // A newer backup that is damaged is passed over
+        let damaged = policy.path(good.taken_at + Duration::hours(1), None);
+        fs::write(&damaged, "This is not a database").unwrap();
+        assert_eq!(policy.backups().unwrap()[0].path, damaged);
+
+        assert_eq!(Connection::latest_good_backup(&policy).await.unwrap(), Some(good));
+    }
+
+    #[tokio::test]
+    async fn test_backup_if_due_waits_for_interval() {
+        let path = database_in_temp_dir("backup_if_due");
+        let policy = BackupPolicy::for_database(&path);
+        let db = Connection::with_new_database(&path).await.unwrap();
+
+        assert!(db.backup_if_due(&policy).await.unwrap().is_some());
+        assert!(db.backup_if_due(&policy).await.unwrap().is_none());
+        assert_eq!(policy.backups().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_new_snapshots_database_before_upgrading() {
+        let path = database_in_temp_dir("snapshot_before_upgrade");
+
+        let mut conn = SqliteConnectOptions::from_str(&path).unwrap()
+            .create_if_missing(true)
+            .connect()
+            .await
+            .unwrap();
+        sqlx::query(include_str!("../resources/schema.sql"))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+
+        Connection::new(&path).await.unwrap();
+        // Already upgraded, so there's nothing to snapshot
+        Connection::new(&path).await.unwrap();
+
+        let backups = BackupPolicy::for_database(&path).backups().unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].label, Some(format!("before-{}", migrations::MIGRATIONS.last().unwrap().name)));
+    }
+
+    #[tokio::test]
+    async fn test_watch_detects_commits_by_other_connections() {
+        let path = database_in_temp_dir("watch");
+        let db = Connection::with_new_database(&path).await.unwrap();
+        let mut detector = db.watch(std::time::Duration::from_millis(10)).await.unwrap();
+        let wait = std::time::Duration::from_millis(500);
+
+        let journal_mode: String = sqlx::query("PRAGMA journal_mode").fetch_one(&db.pool).await.unwrap().get(0);
+        assert_eq!(journal_mode, "wal");
+
+        assert!(tokio::time::timeout(wait, detector.changed()).await.is_err());
+
+        let other = Connection::new(&path).await.unwrap();
+        other.create_task(&Task::default()).await.unwrap();
+
+        assert!(tokio::time::timeout(wait, detector.changed()).await.unwrap().is_ok());
+        assert!(tokio::time::timeout(wait, detector.changed()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_watch_ignores_commits_by_the_watched_connection() {
+        let path = database_in_temp_dir("watch_own");
+        let db = Connection::with_new_database(&path).await.unwrap();
+        let mut detector = db.watch(std::time::Duration::from_millis(10)).await.unwrap();
+        let wait = std::time::Duration::from_millis(500);
+
+        let task = db.create_task(&Task::default()).await.unwrap();
+        db.clone().update_task(&Task { name: "Renamed".into(), ..task }).await.unwrap();
+        assert!(tokio::time::timeout(wait, detector.changed()).await.is_err());
+
+        let other = Connection::new(&path).await.unwrap();
+        other.create_task(&Task::default()).await.unwrap();
+        assert!(tokio::time::timeout(wait, detector.changed()).await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_categories_are_distinct_and_sorted() {
+        let category = |name: &str, category: &str| Task {
+            name: name.into(),
+            category: category.into(),
+            ..Task::default()
+        };
+        let (db, tasks) = Fixture::new()
+            .task(category("A", "Work"))
+            .task(category("B", "Home"))
+            .task(category("C", "Work"))
+            .task(category("D", "Trash"))
+            .build()
+            .await;
+
+        db.delete_task(tasks[3].clone()).await.unwrap();
+
+        assert_eq!(db.categories().await.unwrap(), vec!["Home", "Work"]);
+    }
+
+    #[tokio::test]
+    async fn test_vacation_days_are_added_and_deleted() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let (db, _) = Fixture::new()
+            .vacation_day(day(3))
+            .vacation_day(day(2))
+            .build()
+            .await;
+
+        assert_eq!(db.vacation_days().await.unwrap(), vec![day(2), day(3)]);
+        assert!(db.add_vacation_day(&day(2)).await.is_err());
+
+        db.delete_vacation_day(&day(2)).await.unwrap();
+        // Deleting a day that isn't a vacation day does nothing
+        db.delete_vacation_day(&day(4)).await.unwrap();
+
+        assert_eq!(db.vacation_days().await.unwrap(), vec![day(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_days_off_are_holidays_and_vacation_days() {
+        let holiday = NaiveDate::from_ymd_opt(today_date().year(), 1, 1).unwrap();
+        let vacation = holiday.succ_opt().unwrap();
+        let (db, _) = Fixture::new()
+            .vacation_day(vacation)
+            .build()
+            .await;
+        db.try_update_holidays(&FixedHolidays::new("fixed", &[holiday]), holiday.year()..=holiday.year()).await.unwrap();
+
+        assert_eq!(db.days_off().await.unwrap(), vec![
+            DayOff::whole_day(holiday, Reason::StatHoliday),
+            DayOff::whole_day(vacation, Reason::Vacation),
+        ]);
+        assert_eq!(db.holidays().await.unwrap(), vec![holiday]);
+    }
+
+    #[tokio::test]
+    async fn test_days_off_are_added_and_deleted_in_bulk() {
+        let day = |d| NaiveDate::from_ymd_opt(2030, 7, d).unwrap();
+        let afternoon = HourRange::new(NaiveTime::from_hms_opt(12, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+        let (db, _) = Fixture::new()
+            .vacation_day(day(3))
+            .build()
+            .await;
+
+        let mut trip = DayOff::range(day(1), day(5), Reason::Travel, "Conference", None);
+        trip.push(DayOff { label: "Dentist".into(), hours: afternoon, ..DayOff::whole_day(day(8), Reason::Vacation) });
+        db.add_days_off(&trip).await.unwrap();
+
+        // The vacation day that was already booked stays as it was
+        let days_off = db.days_off().await.unwrap();
+        assert_eq!(days_off.iter().map(|d| (d.date, d.reason)).collect::<Vec<_>>(), vec![
+            (day(1), Reason::Travel),
+            (day(2), Reason::Travel),
+            (day(3), Reason::Vacation),
+            (day(4), Reason::Travel),
+            (day(5), Reason::Travel),
+            (day(8), Reason::Vacation),
+        ]);
+        assert_eq!(days_off[5], trip[5]);
+
+        db.delete_days_off(&[day(1), day(2), day(3)]).await.unwrap();
+        assert_eq!(db.days_off().await.unwrap().len(), 3);
+
+        // Undoing the deletion puts back all three days, labels and all
+        db.undo().await.unwrap();
+        assert_eq!(db.days_off().await.unwrap(), days_off);
+
+        // Undoing the trip removes every day of it, but not the vacation day
+        db.undo().await.unwrap();
+        assert_eq!(db.days_off().await.unwrap(), vec![DayOff::whole_day(day(3), Reason::Vacation)]);
+
+        assert!(db.add_days_off(&[DayOff::whole_day(day(9), Reason::StatHoliday)]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_schedule_uses_stored_holidays_when_offline() {
+        let year = today_date().year();
+        let holiday = NaiveDate::from_ymd_opt(year, 7, 1).unwrap();
+        let db = Connection::in_memory().await.unwrap();
+        assert_eq!(db.holidays_fetched(year).await.unwrap(), None);
+
+        assert!(db.try_update_holidays(&FixedHolidays::new("bc", &[holiday]), year..=year).await.unwrap());
+        let fetched = db.holidays_fetched(year).await.unwrap().unwrap();
+        assert_eq!((fetched.year, fetched.source.as_str()), (year, "bc"));
+
+        assert!(db.try_update_holidays(&FailingHolidays, year..=year).await.is_err());
+        assert_eq!(db.holidays_fetched(year).await.unwrap(), Some(fetched));
+        let schedule = db.schedule(WorkWeek::default(), &[], &Strategy::default()).await.unwrap();
+        assert!(!schedule.is_work_day(holiday));
+    }
+
+    #[tokio::test]
+    async fn test_holidays_are_fetched_once_per_source() {
+        let year = today_date().year();
+        let day = |month: u32| NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+        let db = Connection::in_memory().await.unwrap();
+
+        let bc = FixedHolidays::new("bc", &[day(1), day(9)]);
+        assert!(db.try_update_holidays(&bc, year..=year).await.unwrap());
+        assert!(!db.try_update_holidays(&bc, year..=year).await.unwrap());
+        assert_eq!(bc.requests(), 1);
+        assert_eq!(db.holidays().await.unwrap(), vec![day(1), day(9)]);
+
+        // Changing the source replaces this year's holidays, and keeps vacation days
+        db.add_vacation_day(&day(7)).await.unwrap();
+        let on = FixedHolidays::new("on", &[day(1), day(7)]);
+        db.try_update_holidays(&on, year..=year).await.unwrap();
+        assert_eq!(on.requests(), 1);
+        assert_eq!(db.holidays().await.unwrap(), vec![day(1)]);
+        assert_eq!(db.vacation_days().await.unwrap(), vec![day(7)]);
+
+        // Failing to fetch holidays leaves the stored ones alone
+        assert!(db.try_update_holidays(&FailingHolidays, year..=year).await.is_err());
+        assert_eq!(db.holidays().await.unwrap(), vec![day(1)]);
+    }
+
+    #[tokio::test]
+    async fn test_holidays_are_fetched_for_each_year() {
+        let new_years_day = |year| NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+        let db = Connection::in_memory().await.unwrap();
+
+        let provider = FixedHolidays::new("fixed", &[new_years_day(2030), new_years_day(2031), new_years_day(2032)])
+            .offline_in(2031);
+        assert!(db.try_update_holidays(&provider, 2030..=2031).await.is_err());
+        // The year that could be fetched is stored anyway
+        assert_eq!(db.holidays().await.unwrap(), vec![new_years_day(2030)]);
+        assert!(db.holidays_fetched(2030).await.unwrap().is_some());
+        assert_eq!(db.holidays_fetched(2031).await.unwrap(), None);
+
+        // Only the years not yet fetched are fetched again
+        let provider = FixedHolidays::new("fixed", &[new_years_day(2030), new_years_day(2031), new_years_day(2032)]);
+        assert!(db.try_update_holidays(&provider, 2030..=2032).await.unwrap());
+        assert_eq!(provider.requests(), 2);
+        assert_eq!(db.holidays().await.unwrap(), vec![new_years_day(2030), new_years_day(2031), new_years_day(2032)]);
+    }
+
+    #[tokio::test]
+    async fn test_holidays_from_a_file_are_read_again_once_it_changes() {
+        let day = |month| NaiveDate::from_ymd_opt(2030, month, 1).unwrap();
+        let path = std::env::temp_dir().join(format!("beavor_changing_holidays_{}.json", std::process::id()));
+        let provider = crate::holidays::JsonFile::new(path.clone());
+        let db = Connection::in_memory().await.unwrap();
+
+        fs::write(&path, "[\"2030-01-01\"]").unwrap();
+        assert!(db.try_update_holidays(&provider, 2030..=2030).await.unwrap());
+        assert!(!db.try_update_holidays(&provider, 2030..=2030).await.unwrap());
+
+        fs::write(&path, "[\"2030-07-01\"]").unwrap();
+        // Modified times are only as fine as the stored time it was read at, so step past it
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(2);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+        assert!(db.try_update_holidays(&provider, 2030..=2030).await.unwrap());
+        assert_eq!(db.holidays().await.unwrap(), vec![day(7)]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_schedule_skips_days_off() {
+        let today = today_date();
+        let mut days = DateIterator::new(today.succ_opt().unwrap(), Some(today + Duration::days(30)))
+            .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun));
+        let vacation = days.next().unwrap();
+        let due = days.nth(3).unwrap();
+
+        let (db, tasks) = Fixture::new()
+            .vacation_day(vacation)
+            .task(Task {
+                name: "Task".into(),
+                time_needed: Duration::hours(20),
+                next_action_date: vacation,
+                due_date: DueDate::Date(due),
+                ..Task::default()
+            })
+            .build()
+            .await;
+
+        let schedule = db.schedule(WorkWeek::default(), &tasks, &Strategy::default()).await.unwrap();
+
+        assert!(!schedule.is_work_day(vacation));
+        assert!(schedule.get(vacation).is_none());
+
+        // All of the task's time is assigned, to the work days from its next action to its due date
+        let assigned = DateIterator::new(vacation, Some(due))
+            .filter_map(|d| schedule.get_time_assigned_on_day(d))
+            .fold(Duration::zero(), |total, time| total + time);
+        assert_eq!(assigned, Duration::hours(20));
+    }
+
+    #[tokio::test]
+    async fn test_move_task_between_databases() {
+        let (work, tasks) = Fixture::new()
+            .task_with_links("Task", &["a", "b"])
+            .build()
+            .await;
+        let (personal, _) = Fixture::new().build().await;
+
+        let moved = work.move_task(tasks[0].clone(), &personal).await.unwrap();
+
+        assert!(work.open_tasks().await.unwrap().is_empty());
+        assert_eq!(work.trashed_tasks().await.unwrap()[0].task, tasks[0]);
+        assert_eq!(personal.open_tasks().await.unwrap(), vec![moved.clone()]);
+        assert_eq!(moved.name, "Task");
+        assert_eq!(urls(&moved), vec!["a", "b"]);
+
+        // A task that isn't in the source database is not copied, and leaves nothing behind
+        let undo_log = |db: &Connection| {
+            let pool = db.pool.clone();
+            async move {
+                sqlx::query("SELECT COUNT(*) FROM undo_log").fetch_one(&pool).await.unwrap().get::<i64, _>(0)
+            }
+        };
+        let logged = undo_log(&personal).await;
+        assert!(matches!(work.move_task(moved.clone(), &personal).await, Err(BeavorError::NotFound(_))));
+        assert_eq!(personal.open_tasks().await.unwrap(), vec![moved.clone()]);
+        assert!(personal.trashed_tasks().await.unwrap().is_empty());
+        assert_eq!(undo_log(&personal).await, logged);
+        assert!(personal.task_history(moved.id.unwrap() + 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_missing_task_is_not_found() {
+        let db = Connection::in_memory().await.unwrap();
+
+        let mut task = task_with_links("Task", &[]);
+        task.id = Some(42);
+
+        assert!(matches!(db.update_task(&task).await, Err(BeavorError::NotFound(_))));
     }
 }
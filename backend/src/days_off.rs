@@ -0,0 +1,126 @@
+//! Days, or parts of days, when there is no work, as stored in the `days_off` table
+
+use core::fmt::Display;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::schedule::{
+    DateIterator,
+    HourRange,
+};
+
+/// Why a day is off, matching the `Reason` column of `days_off`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    Vacation,
+    StatHoliday,
+    Travel,
+}
+
+impl Reason {
+    /// Pure
+    #[must_use] pub fn as_str(self) -> &'static str {
+        match self {
+            Reason::Vacation    => "vacation",
+            Reason::StatHoliday => "stat_holiday",
+            Reason::Travel      => "travel",
+        }
+    }
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Reason::Vacation    => "Vacation",
+            Reason::StatHoliday => "Holiday",
+            Reason::Travel      => "Travel",
+        })
+    }
+}
+
+impl FromStr for Reason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vacation"     => Ok(Reason::Vacation),
+            "stat_holiday" => Ok(Reason::StatHoliday),
+            "travel"       => Ok(Reason::Travel),
+            other => Err(format!("unknown reason for a day off: {other}")),
+        }
+    }
+}
+
+/// A day off, or the part of one that is off
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayOff {
+    pub date:   NaiveDate,
+    pub reason: Reason,
+    /// Anything the user wants to note about the day, e.g., where they're travelling to
+    pub label:  String,
+    /// The hours that are off, e.g., only the afternoon. `None` means the whole day.
+    pub hours:  Option<HourRange>,
+}
+
+impl DayOff {
+    /// Pure
+    #[must_use] pub fn whole_day(date: NaiveDate, reason: Reason) -> Self {
+        Self {
+            date,
+            reason,
+            label: String::new(),
+            hours: None,
+        }
+    }
+
+    /// Pure
+    ///
+    /// Returns a day off like this one on every day from `start` to `end`, including both, e.g.,
+    /// for a two-week vacation. Nothing if `end` is before `start`.
+    #[must_use] pub fn range(start: NaiveDate, end: NaiveDate, reason: Reason, label: &str, hours: Option<HourRange>) -> Vec<Self> {
+        DateIterator::new(start, Some(end))
+            .map(|date| Self {
+                date,
+                reason,
+                label: label.to_string(),
+                hours,
+            })
+            .collect()
+    }
+
+    /// Pure
+    #[must_use] pub fn is_whole_day(&self) -> bool {
+        self.hours.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_includes_both_ends() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 12, d).unwrap();
+
+        let days = DayOff::range(day(23), day(27), Reason::Vacation, "Home for the holidays", None);
+
+        assert_eq!(days.iter().map(|d| d.date).collect::<Vec<_>>(), vec![day(23), day(24), day(25), day(26), day(27)]);
+        assert!(days.iter().all(|d| d.label == "Home for the holidays" && d.is_whole_day()));
+        assert!(DayOff::range(day(27), day(23), Reason::Travel, "", None).is_empty());
+    }
+
+    #[test]
+    fn test_reason_round_trips_through_its_column() {
+        for reason in [Reason::Vacation, Reason::StatHoliday, Reason::Travel] {
+            assert_eq!(reason.as_str().parse::<Reason>(), Ok(reason));
+        }
+        assert!("sick".parse::<Reason>().is_err());
+    }
+}
@@ -6,6 +6,11 @@ use core::fmt::Display;
 
 use std::cmp::Ordering;
 
+use anyhow::{
+    anyhow,
+    bail,
+};
+
 use crate::utils::{
     format_date_borrowed,
     parse_date,
@@ -19,10 +24,41 @@ pub enum DueDate {
     Asap,
 }
 
+/// Values of the `DueKind` column, in the same order as `impl Ord for DueDate`
+pub(crate) const DUE_ASAP:  i64 = 0;
+pub(crate) const DUE_DATE:  i64 = 1;
+pub(crate) const DUE_NEVER: i64 = 2;
+
 impl DueDate {
     #[must_use] pub fn new() -> Self {
         Self::default()
     }
+
+    /// Pure
+    ///
+    /// Returns the `DueKind` and `DueOn` columns that store this in the tasks table.
+    /// Sorting by `DueKind` and then `DueOn` gives the same order as `Ord`.
+    #[must_use] pub fn to_columns(self) -> (i64, Option<String>) {
+        match self {
+            DueDate::Asap => (DUE_ASAP, None),
+            DueDate::Date(date) => (DUE_DATE, Some(format_date_borrowed(&date))),
+            DueDate::Never => (DUE_NEVER, None),
+        }
+    }
+
+    /// Pure
+    ///
+    /// The inverse of `to_columns`
+    /// # Errors
+    /// Returns an error if `kind` is not a known kind, or if a date is missing or cannot be parsed
+    pub fn from_columns(kind: i64, on: Option<&str>) -> Result<Self, anyhow::Error> {
+        Ok(match kind {
+            DUE_ASAP => DueDate::Asap,
+            DUE_DATE => DueDate::Date(parse_date(on.ok_or_else(|| anyhow!("a due date of kind {DUE_DATE} needs a date"))?)?),
+            DUE_NEVER => DueDate::Never,
+            other => bail!("{other} is not a kind of due date"),
+        })
+    }
 }
 
 impl Default for DueDate {
@@ -134,6 +170,28 @@ mod tests {
         assert_eq!(DueDate::Date(NaiveDate::from_ymd_opt(1971,01,01).unwrap()).to_string(), "1971-01-01");
     }
 
+    #[test]
+    fn test_due_date_columns_sort_like_due_dates() {
+        let mut due_dates = vec![
+            DueDate::Never,
+            DueDate::Date(NaiveDate::from_ymd_opt(1971,01,02).unwrap()),
+            DueDate::Asap,
+            DueDate::Date(NaiveDate::from_ymd_opt(1970,12,31).unwrap()),
+        ];
+
+        let mut by_columns = due_dates.clone();
+        by_columns.sort_by_key(|dd| dd.to_columns());
+        due_dates.sort();
+        assert_eq!(by_columns, due_dates);
+
+        for dd in due_dates {
+            let (kind, on) = dd.to_columns();
+            assert_eq!(DueDate::from_columns(kind, on.as_deref()).unwrap(), dd);
+        }
+        assert!(DueDate::from_columns(DUE_DATE, None).is_err());
+        assert!(DueDate::from_columns(3, None).is_err());
+    }
+
     #[test]
     fn test_due_date_string_parse() {
         for dd in [DueDate::Asap, DueDate::Never, DueDate::Date(NaiveDate::from_ymd_opt(1971,01,01).unwrap())] {
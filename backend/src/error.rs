@@ -0,0 +1,114 @@
+use core::fmt::Display;
+
+use crate::{
+    Task,
+    migrations::MigrationError,
+};
+
+pub type Result<T, E = BeavorError> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while reading or writing the database
+#[derive(Debug)]
+pub enum BeavorError {
+    /// The requested row does not exist, e.g., updating a task that has been deleted
+    NotFound(String),
+    /// The task was updated elsewhere after the copy being saved was read. Holds the task as it
+    /// is stored now.
+    Conflict(Box<Task>),
+    /// A row in the database holds a value that cannot be read
+    InvalidRow {
+        table:  &'static str,
+        /// The `TaskID` of the row, for tasks. `None` when the row has no id or it could not be read
+        id:     Option<u32>,
+        column: &'static str,
+        reason: String,
+    },
+    /// A write was rejected by a `UNIQUE`, `CHECK`, `NOT NULL` or foreign key constraint
+    ConstraintViolation(String),
+    Io(std::io::Error),
+    Network(reqwest::Error),
+    /// A remote server responded, or a holiday file was read, but not with anything that could be
+    /// understood
+    InvalidResponse(String),
+    Migration(MigrationError),
+    /// The database file is damaged, and should be restored from a backup
+    Corrupt(String),
+    Database(sqlx::Error),
+}
+
+impl BeavorError {
+    /// Pure
+    ///
+    /// Convenience constructor for an `InvalidRow` in the tasks table
+    pub(crate) fn invalid_task(id: Option<u32>, column: &'static str, reason: impl Display) -> Self {
+        Self::InvalidRow {
+            table: "tasks",
+            id,
+            column,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Display for BeavorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BeavorError::NotFound(what) => write!(f, "Not found: {what}"),
+            BeavorError::Conflict(stored) => write!(f, "'{}' was changed elsewhere since it was opened", stored.name),
+            BeavorError::InvalidRow { table, id, column, reason } => match id {
+                Some(id) => write!(f, "Invalid {column} in {table} (TaskID {id}): {reason}"),
+                None     => write!(f, "Invalid {column} in {table}: {reason}"),
+            },
+            BeavorError::ConstraintViolation(message) => write!(f, "Constraint violated: {message}"),
+            BeavorError::Io(e) => write!(f, "IO error: {e}"),
+            BeavorError::Network(e) => write!(f, "Network error: {e}"),
+            BeavorError::InvalidResponse(message) => write!(f, "Invalid response: {message}"),
+            BeavorError::Migration(e) => write!(f, "{e}"),
+            BeavorError::Corrupt(problems) => write!(f, "The database is damaged: {problems}"),
+            BeavorError::Database(e) => write!(f, "Database error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BeavorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BeavorError::Io(e) => Some(e),
+            BeavorError::Migration(e) => Some(e),
+            BeavorError::Network(e) => Some(e),
+            BeavorError::Database(e) => Some(e),
+            BeavorError::NotFound(_) | BeavorError::Conflict(_) | BeavorError::InvalidRow {..} | BeavorError::ConstraintViolation(_) | BeavorError::InvalidResponse(_) | BeavorError::Corrupt(_) => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for BeavorError {
+    fn from(value: sqlx::Error) -> Self {
+        match value {
+            sqlx::Error::RowNotFound => Self::NotFound("no matching row in the database".into()),
+            sqlx::Error::Io(e) => Self::Io(e),
+            sqlx::Error::Database(e) if !matches!(e.kind(), sqlx::error::ErrorKind::Other) => Self::ConstraintViolation(e.message().into()),
+            // SQLITE_CORRUPT and SQLITE_NOTADB, ignoring the extended result code in the upper bits
+            sqlx::Error::Database(e) if e.code().and_then(|c| c.parse::<i32>().ok()).is_some_and(|c| matches!(c & 0xff, 11 | 26)) => Self::Corrupt(e.message().into()),
+            e => Self::Database(e),
+        }
+    }
+}
+
+impl From<MigrationError> for BeavorError {
+    fn from(value: MigrationError) -> Self {
+        Self::Migration(value)
+    }
+}
+
+impl From<std::io::Error> for BeavorError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<reqwest::Error> for BeavorError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Network(value)
+    }
+}
@@ -0,0 +1,155 @@
+//! Builders for the databases used in tests
+
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+
+use chrono::{
+    NaiveDate,
+    Datelike,
+};
+
+use futures::future::BoxFuture;
+
+use crate::{
+    DatabaseManager as Connection,
+    Task,
+    Hyperlink,
+    HolidayProvider,
+    error::{
+        BeavorError,
+        Result,
+    },
+};
+
+/// Builds an in-memory database holding the given tasks and vacation days.
+///
+/// Holidays are only stored by `Connection::try_update_holidays`, so pass it a `FixedHolidays`
+/// to add some without the network.
+#[derive(Debug, Default)]
+pub struct Fixture {
+    tasks:         Vec<Task>,
+    vacation_days: Vec<NaiveDate>,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn task(mut self, task: Task) -> Self {
+        self.tasks.push(task);
+        self
+    }
+
+    /// Adds a task with the given name and a link to each of `urls`
+    pub fn task_with_links(self, name: &str, urls: &[&str]) -> Self {
+        self.task(task_with_links(name, urls))
+    }
+
+    pub fn vacation_day(mut self, date: NaiveDate) -> Self {
+        self.vacation_days.push(date);
+        self
+    }
+
+    /// Returns the database, and the tasks as they were created (i.e., with their ids) in the
+    /// order they were added
+    pub async fn build(self) -> (Connection, Vec<Task>) {
+        let db = Connection::in_memory().await.expect("Should be able to create an in-memory database");
+
+        let mut tasks = Vec::with_capacity(self.tasks.len());
+        for task in &self.tasks {
+            tasks.push(db.create_task(task).await.expect("Fixture tasks should be valid"));
+        }
+
+        for date in &self.vacation_days {
+            db.add_vacation_day(date).await.expect("Fixture vacation days should be unique");
+        }
+
+        (db, tasks)
+    }
+}
+
+/// Holidays on the given dates, counting how often they are asked for
+#[derive(Debug)]
+pub struct FixedHolidays {
+    source:   &'static str,
+    dates:    Vec<NaiveDate>,
+    /// Years that fail to be fetched, as if offline
+    offline:  Vec<i32>,
+    requests: AtomicUsize,
+}
+
+impl FixedHolidays {
+    pub fn new(source: &'static str, dates: &[NaiveDate]) -> Self {
+        Self {
+            source,
+            dates: dates.to_vec(),
+            offline: Vec::new(),
+            requests: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn offline_in(mut self, year: i32) -> Self {
+        self.offline.push(year);
+        self
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+impl HolidayProvider for FixedHolidays {
+    fn source(&self) -> String {
+        self.source.into()
+    }
+
+    fn holidays(&self, year: i32) -> BoxFuture<'_, Result<Vec<NaiveDate>>> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        let dates = self.dates.iter().copied().filter(|d| d.year() == year).collect();
+        let offline = self.offline.contains(&year);
+        Box::pin(async move {
+            if offline {
+                return Err(BeavorError::NotFound(format!("holidays in {year} are offline")));
+            }
+            Ok(dates)
+        })
+    }
+}
+
+/// Holidays that can never be fetched, as if offline
+#[derive(Debug)]
+pub struct FailingHolidays;
+
+impl HolidayProvider for FailingHolidays {
+    fn source(&self) -> String {
+        "failing".into()
+    }
+
+    fn holidays(&self, _year: i32) -> BoxFuture<'_, Result<Vec<NaiveDate>>> {
+        Box::pin(async { Err(BeavorError::NotFound("holidays are offline".into())) })
+    }
+}
+
+/// A link whose display text is its url
+pub fn link(url: &str) -> Hyperlink {
+    Hyperlink {
+        url: url.into(),
+        display: url.into(),
+        id: 0,
+    }
+}
+
+pub fn task_with_links(name: &str, urls: &[&str]) -> Task {
+    Task {
+        name: name.into(),
+        links: urls.iter().map(|u| link(u)).collect(),
+        ..Task::default()
+    }
+}
+
+pub fn urls(task: &Task) -> Vec<String> {
+    task.links.iter().map(|h| h.url.clone()).collect()
+}
@@ -0,0 +1,154 @@
+use core::fmt::Display;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+
+use crate::{
+    Task,
+    DueDate,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Display for ChangeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ChangeAction::Create => "create",
+            ChangeAction::Update => "update",
+            ChangeAction::Delete => "delete",
+        })
+    }
+}
+
+impl FromStr for ChangeAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        match action {
+            "create" => Ok(ChangeAction::Create),
+            "update" => Ok(ChangeAction::Update),
+            "delete" => Ok(ChangeAction::Delete),
+            other    => Err(format!("'{other}' is not a change action")),
+        }
+    }
+}
+
+/// A change to a single field of a task, as recorded in `task_history`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TaskChange {
+    pub task_id:    u32,
+    pub changed_at: NaiveDateTime,
+    pub changed_by: String,
+    pub action:     ChangeAction,
+    pub field:      String,
+    /// `None` when the task is being created
+    pub old_value:  Option<String>,
+    /// `None` when the task is being deleted
+    pub new_value:  Option<String>,
+}
+
+/// A field that differs between two versions of a task, before it is given a time and author
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub field:     &'static str,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// Pure
+///
+/// The fields of a task that are tracked in its history, formatted for display.
+/// Names match the columns of the tasks table.
+fn tracked_fields(task: &Task) -> [(&'static str, String); 10] {
+    [
+        ("Category",     task.category.clone()),
+        ("Finished",     task.finished.to_string()),
+        ("Name",         task.name.clone()),
+        ("Time",         task.time_needed.num_seconds().to_string()),
+        ("Used",         task.time_used.num_seconds().to_string()),
+        ("NextAction",   DueDate::Date(task.next_action_date).to_string()),
+        ("DueDate",      task.due_date.to_string()),
+        ("Notes",        task.notes.clone()),
+        ("FinishedDate", task.finished_date.map_or_else(String::new, |d| d.to_string())),
+        ("Links",        task.links
+            .iter()
+            .map(|h| format!("{} <{}>", h.display, h.url))
+            .collect::<Vec<_>>()
+            .join("\n")),
+    ]
+}
+
+/// Pure
+///
+/// Returns the fields that differ between `old` and `new`.
+/// Pass `None` for `old` when creating a task and for `new` when deleting one; every field is
+/// then reported.
+#[must_use] pub fn diff(old: Option<&Task>, new: Option<&Task>) -> Vec<FieldDiff> {
+    let names = tracked_fields(&Task::default()).map(|(name, _)| name);
+    let values = |task: Option<&Task>| task.map_or_else(
+        Default::default,
+        |t| tracked_fields(t).map(|(_, value)| Some(value))
+    );
+
+    names.into_iter()
+        .zip(values(old))
+        .zip(values(new))
+        .filter(|((_, old_value), new_value)| old_value != new_value)
+        .map(|((field, old_value), new_value)| FieldDiff {
+            field,
+            old_value,
+            new_value,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_diff_update_reports_changed_fields_only() {
+        let old = Task::default();
+        let new = Task {
+            name: "Renamed".into(),
+            time_needed: Duration::minutes(90),
+            ..old.clone()
+        };
+
+        assert_eq!(
+            diff(Some(&old), Some(&new)),
+            vec![
+                FieldDiff { field: "Name", old_value: Some(String::new()), new_value: Some("Renamed".into()) },
+                FieldDiff { field: "Time", old_value: Some("0".into()),    new_value: Some("5400".into()) },
+            ]
+        );
+        assert!(diff(Some(&old), Some(&old)).is_empty());
+    }
+
+    #[test]
+    fn test_diff_create_and_delete_report_every_field() {
+        let task = Task::default();
+
+        let created = diff(None, Some(&task));
+        assert_eq!(created.len(), 10);
+        assert!(created.iter().all(|d| d.old_value.is_none() && d.new_value.is_some()));
+
+        let deleted = diff(Some(&task), None);
+        assert_eq!(deleted.len(), 10);
+        assert!(deleted.iter().all(|d| d.old_value.is_some() && d.new_value.is_none()));
+    }
+
+    #[test]
+    fn test_change_action_string_parse() {
+        for action in [ChangeAction::Create, ChangeAction::Update, ChangeAction::Delete] {
+            assert_eq!(ChangeAction::from_str(&action.to_string()).unwrap(), action);
+        }
+    }
+}
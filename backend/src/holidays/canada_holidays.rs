@@ -0,0 +1,158 @@
+use chrono::{
+    NaiveDate,
+    Datelike,
+};
+
+use futures::future::BoxFuture;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::{
+    HolidayProvider,
+    error::{
+        BeavorError,
+        Result,
+    },
+};
+
+const URL: &str = "https://canada-holidays.ca/api/v1/holidays";
+
+/// Holidays from the canada-holidays.ca API, for a single province or territory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanadaHolidays {
+    url:      String,
+    province: String,
+}
+
+impl CanadaHolidays {
+    /// Pure
+    ///
+    /// `province` is the two-letter abbreviation of a province or territory, e.g., `BC`
+    #[must_use] pub fn new(province: &str) -> Self {
+        Self::with_url(URL, province)
+    }
+
+    /// Pure
+    ///
+    /// Uses a server other than canada-holidays.ca that has the same API, e.g., for testing
+    #[must_use] pub fn with_url(url: &str, province: &str) -> Self {
+        Self {
+            url:      url.into(),
+            province: province.to_uppercase(),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+#[derive(Serialize, Deserialize)]
+struct Province {
+    id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Holidays {
+    holidays: Vec<Holiday>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+struct Holiday {
+    provinces: Vec<Province>,
+    observedDate: String
+}
+
+impl HolidayProvider for CanadaHolidays {
+    fn source(&self) -> String {
+        format!("canada-holidays.ca/{}", self.province)
+    }
+
+    fn holidays(&self, year: i32) -> BoxFuture<'_, Result<Vec<NaiveDate>>> {
+        Box::pin(async move {
+            let response: String = reqwest::get(format!("{}?year={year}", self.url))
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+
+            let province = Province {id: self.province.clone()};
+
+            let dates = serde_json::from_str::<Holidays>(&response)
+                .map_err(|e| BeavorError::InvalidResponse(e.to_string()))?
+                .holidays
+                .iter()
+                .filter(|h| h.provinces.contains(&province))
+                .map(|h| h.observedDate.parse::<NaiveDate>())
+                .collect::<Result<Vec<NaiveDate>, _>>()
+                .map_err(|e| BeavorError::InvalidResponse(e.to_string()))?;
+
+            Ok(dates.into_iter().filter(|d| d.year() == year).collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{
+        Read,
+        Write,
+    };
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    use super::*;
+
+    /// Serves a single HTTP response with `body` on localhost. Returns the url to request, and
+    /// receives the request line that was served.
+    fn stub_server(status: &'static str, body: &'static str) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/v1/holidays", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..read]);
+            // Tests that don't check the request drop the receiver
+            let _ = tx.send(request.lines().next().unwrap_or_default().to_string());
+
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            ).unwrap();
+        });
+
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_holidays_for_province() {
+        let (url, requests) = stub_server("200 OK", r#"{"holidays": [
+            {"observedDate": "2024-01-01", "provinces": [{"id": "BC"}, {"id": "ON"}]},
+            {"observedDate": "2024-02-19", "provinces": [{"id": "ON"}]},
+            {"observedDate": "2024-09-30", "provinces": [{"id": "BC"}]}
+        ]}"#);
+
+        let provider = CanadaHolidays::with_url(&url, "on");
+
+        assert_eq!(provider.source(), "canada-holidays.ca/ON");
+        assert_eq!(provider.holidays(2024).await.unwrap(), vec![
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 19).unwrap(),
+        ]);
+        assert_eq!(requests.recv().unwrap(), "GET /api/v1/holidays?year=2024 HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_are_reported() {
+        let (url, _) = stub_server("500 Internal Server Error", "");
+        assert!(matches!(CanadaHolidays::with_url(&url, "BC").holidays(2024).await, Err(BeavorError::Network(_))));
+
+        let (url, _) = stub_server("200 OK", "<html>Not JSON</html>");
+        assert!(matches!(CanadaHolidays::with_url(&url, "BC").holidays(2024).await, Err(BeavorError::InvalidResponse(_))));
+    }
+}
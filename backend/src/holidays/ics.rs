@@ -0,0 +1,254 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{
+    NaiveDate,
+    NaiveDateTime,
+    Datelike,
+};
+
+use futures::future::BoxFuture;
+
+use crate::{
+    HolidayProvider,
+    error::{
+        BeavorError,
+        Result,
+    },
+};
+
+/// Holidays from the events in an iCalendar (`.ics`) file, e.g., exported from a calendar app.
+/// Every day that an event covers is a holiday. Events that recur yearly are holidays every year
+/// they recur; a file with any other recurring event is rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcsFile {
+    path: PathBuf,
+}
+
+impl IcsFile {
+    /// Pure
+    #[must_use] pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+        }
+    }
+}
+
+impl HolidayProvider for IcsFile {
+    fn source(&self) -> String {
+        format!("ics:{}", self.path.display())
+    }
+
+    fn changed_since(&self, fetched_at: NaiveDateTime) -> bool {
+        super::modified_since(&self.path, fetched_at)
+    }
+
+    fn holidays(&self, year: i32) -> BoxFuture<'_, Result<Vec<NaiveDate>>> {
+        Box::pin(async move {
+            let ics = fs::read_to_string(&self.path)?;
+
+            parse(&ics, year)
+                .map_err(|e| BeavorError::InvalidResponse(format!("{}: {e}", self.path.display())))
+        })
+    }
+}
+
+/// Pure
+///
+/// Returns every day in `year` covered by an event in the calendar, in the order they appear
+fn parse(ics: &str, year: i32) -> Result<Vec<NaiveDate>, String> {
+    // Long lines are folded by starting each continuation with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut dates = Vec::new();
+    let mut start = None;
+    let mut end = None;
+    let mut rule = None;
+
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters, e.g., `;VALUE=DATE`, come after the property name
+        let name = name.split(';').next().unwrap_or_default();
+
+        match (name, value) {
+            ("BEGIN", "VEVENT") => (start, end, rule) = (None, None, None),
+            ("DTSTART", value) => start = Some(parse_date(value)?),
+            ("DTEND", value) => end = Some(parse_date(value)?),
+            // Time zones have rules too, so these are only read once the event is known
+            ("RRULE", value) => rule = Some(value.to_string()),
+            ("END", "VEVENT") => {
+                let start = start.ok_or("an event has no DTSTART")?;
+                // The end is exclusive, and an event without one lasts a day
+                let days = end.filter(|end| *end > start).map_or(1, |end| (end - start).num_days());
+                let starts = match &rule {
+                    Some(rule) => YearlyRule::parse(rule, start)?.occurrences(start, year),
+                    None => vec![start],
+                };
+                for start in starts {
+                    dates.extend(start.iter_days().take_while(|d| (*d - start).num_days() < days).filter(|d| d.year() == year));
+                }
+            },
+            _ => (),
+        }
+    }
+
+    Ok(dates)
+}
+
+/// How an event recurs every year or every few years, from an `RRULE` with `FREQ=YEARLY`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct YearlyRule {
+    interval: i32,
+    count:    Option<usize>,
+    until:    Option<NaiveDate>,
+}
+
+impl YearlyRule {
+    /// Pure
+    ///
+    /// Parses the value of an `RRULE` for an event starting on `start`. Parts that only repeat
+    /// the start date, e.g., `BYMONTH=1`, are allowed, but any others are rejected, as are rules
+    /// that don't recur yearly.
+    fn parse(rule: &str, start: NaiveDate) -> Result<Self, String> {
+        let mut yearly = false;
+        let mut parsed = Self {
+            interval: 1,
+            count:    None,
+            until:    None,
+        };
+        let unsupported = || format!("the recurrence '{rule}' is not supported, only events that recur on the same date every year");
+
+        for part in rule.split(';') {
+            let (name, value) = part.split_once('=').ok_or_else(unsupported)?;
+            match name {
+                "FREQ" => yearly = value == "YEARLY",
+                "INTERVAL" => parsed.interval = value.parse().ok().filter(|i| *i > 0).ok_or_else(unsupported)?,
+                "COUNT" => parsed.count = Some(value.parse().map_err(|_| unsupported())?),
+                "UNTIL" => parsed.until = Some(parse_date(value)?),
+                "BYMONTH" if value.parse() == Ok(start.month()) => (),
+                "BYMONTHDAY" if value.parse() == Ok(start.day()) => (),
+                "WKST" => (),
+                _ => return Err(unsupported()),
+            }
+        }
+
+        if yearly { Ok(parsed) } else { Err(unsupported()) }
+    }
+
+    /// Pure
+    ///
+    /// Returns the days each occurrence starts on that could cover a day of `year`, i.e., those
+    /// starting that year or the one before. Occurrences that would be on a date that doesn't
+    /// exist, e.g., February 29th of a year that isn't a leap year, are skipped.
+    fn occurrences(&self, start: NaiveDate, year: i32) -> Vec<NaiveDate> {
+        // Skipped dates are not counted towards the rule's COUNT
+        (start.year()..=year)
+            .step_by(usize::try_from(self.interval).unwrap_or(1))
+            .filter_map(|y| start.with_year(y))
+            .take(self.count.unwrap_or(usize::MAX))
+            .take_while(|occurrence| self.until.is_none_or(|until| *occurrence <= until))
+            .filter(|occurrence| occurrence.year() >= year - 1)
+            .collect()
+    }
+}
+
+/// Pure
+///
+/// Parses the date of a `DATE` (`20240101`) or `DATE-TIME` (`20240101T090000Z`) value
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    value.get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| format!("'{value}' is not a date"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:New Year's\r\n  Day\r\n\
+            DTSTART;VALUE=DATE:20240101\r\n\
+            DTEND;VALUE=DATE:20240102\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Shutdown\r\n\
+            DTSTART;VALUE=DATE:20241224\r\n\
+            DTEND;VALUE=DATE:20241227\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20240701T000000Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        assert_eq!(parse(ics, 2024).unwrap(), vec![day(1, 1), day(12, 24), day(12, 25), day(12, 26), day(7, 1)]);
+        assert!(parse(ics, 2025).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_dates() {
+        assert!(parse("BEGIN:VEVENT\nDTSTART:2024\nEND:VEVENT", 2024).is_err());
+        assert!(parse("BEGIN:VEVENT\nSUMMARY:No start\nEND:VEVENT", 2024).is_err());
+    }
+
+    #[test]
+    fn test_parse_yearly_events() {
+        let event = |start: &str, end: &str, rule: &str| format!(
+            "BEGIN:VEVENT\nDTSTART;VALUE=DATE:{start}\nDTEND;VALUE=DATE:{end}\nRRULE:{rule}\nEND:VEVENT\n"
+        );
+        let ics = [
+            event("20200101", "20200102", "FREQ=YEARLY"),
+            event("20201231", "20210102", "FREQ=YEARLY;BYMONTH=12;BYMONTHDAY=31"),
+            event("20220701", "20220702", "FREQ=YEARLY;INTERVAL=2"),
+            event("20200301", "20200302", "FREQ=YEARLY;COUNT=3"),
+            event("20200401", "20200402", "FREQ=YEARLY;UNTIL=20231231T000000Z"),
+        ].concat();
+
+        assert_eq!(parse(&ics, 2024).unwrap(), vec![day(1, 1), day(1, 1), day(12, 31), day(7, 1)]);
+        // The event over New Year's covers two days of 2022
+        assert_eq!(parse(&ics, 2022).unwrap().len(), 6);
+        // Not before the event starts
+        assert!(parse(&ics, 2019).unwrap().is_empty());
+        // February 29th only recurs in leap years
+        let leap_day = event("20200229", "20200301", "FREQ=YEARLY");
+        assert!(parse(&leap_day, 2023).unwrap().is_empty());
+        assert_eq!(parse(&leap_day, 2024).unwrap(), vec![NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()]);
+    }
+
+    #[test]
+    fn test_parse_rejects_other_recurrences() {
+        let event = |rule: &str| format!("BEGIN:VEVENT\nDTSTART;VALUE=DATE:20240101\nRRULE:{rule}\nEND:VEVENT\n");
+
+        assert!(parse(&event("FREQ=WEEKLY"), 2024).is_err());
+        assert!(parse(&event("FREQ=YEARLY;BYMONTH=10;BYDAY=2MO"), 2024).is_err());
+        assert!(parse(&event("FREQ=YEARLY;BYMONTH=2"), 2024).is_err());
+        // Time zones have rules of their own, which are not events
+        let time_zone = "BEGIN:VTIMEZONE\nBEGIN:STANDARD\nDTSTART:19701101T020000\nRRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\nEND:STANDARD\nEND:VTIMEZONE\n";
+        assert_eq!(parse(&(time_zone.to_string() + &event("FREQ=YEARLY")), 2024).unwrap(), vec![day(1, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_holidays_are_read_from_file() {
+        let path = std::env::temp_dir().join(format!("beavor_holidays_{}.ics", std::process::id()));
+        fs::write(&path, "BEGIN:VEVENT\nDTSTART;VALUE=DATE:20240101\nEND:VEVENT\nBEGIN:VEVENT\nDTSTART;VALUE=DATE:20250101\nEND:VEVENT\n").unwrap();
+
+        assert_eq!(IcsFile::new(path.clone()).holidays(2024).await.unwrap(), vec![day(1, 1)]);
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(IcsFile::new(path).holidays(2024).await, Err(BeavorError::Io(_))));
+    }
+}
@@ -0,0 +1,101 @@
+//! Holidays listed in a JSON file, either as dates or as objects with a date, e.g.,
+//!
+//! ```json
+//! [
+//!     "2024-01-01",
+//!     {"date": "2024-07-01", "name": "Canada Day"}
+//! ]
+//! ```
+//!
+//! Any other fields of an object are ignored.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{
+    NaiveDate,
+    NaiveDateTime,
+    Datelike,
+};
+
+use futures::future::BoxFuture;
+
+use serde::Deserialize;
+
+use crate::{
+    HolidayProvider,
+    error::{
+        BeavorError,
+        Result,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonFile {
+    path: PathBuf,
+}
+
+impl JsonFile {
+    /// Pure
+    #[must_use] pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Date(NaiveDate),
+    Holiday {
+        date: NaiveDate,
+    },
+}
+
+impl HolidayProvider for JsonFile {
+    fn source(&self) -> String {
+        format!("json:{}", self.path.display())
+    }
+
+    fn changed_since(&self, fetched_at: NaiveDateTime) -> bool {
+        super::modified_since(&self.path, fetched_at)
+    }
+
+    fn holidays(&self, year: i32) -> BoxFuture<'_, Result<Vec<NaiveDate>>> {
+        Box::pin(async move {
+            let json = fs::read_to_string(&self.path)?;
+
+            Ok(serde_json::from_str::<Vec<Entry>>(&json)
+                .map_err(|e| BeavorError::InvalidResponse(format!("{}: {e}", self.path.display())))?
+                .into_iter()
+                .map(|entry| match entry {
+                    Entry::Date(date) | Entry::Holiday { date } => date,
+                })
+                .filter(|d| d.year() == year)
+                .collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_holidays_are_read_from_file() {
+        let path = std::env::temp_dir().join(format!("beavor_holidays_{}.json", std::process::id()));
+        let provider = JsonFile::new(path.clone());
+
+        fs::write(&path, r#"["2024-01-01", {"date": "2024-07-01", "name": "Canada Day"}, "2025-01-01"]"#).unwrap();
+        assert_eq!(provider.holidays(2024).await.unwrap(), vec![
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 7, 1).unwrap(),
+        ]);
+
+        fs::write(&path, r#"["January 1st"]"#).unwrap();
+        assert!(matches!(provider.holidays(2024).await, Err(BeavorError::InvalidResponse(_))));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
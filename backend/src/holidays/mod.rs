@@ -0,0 +1,185 @@
+//! Where stat holidays come from.
+//!
+//! Holidays are fetched a year at a time by a `HolidayProvider`, and stored by
+//! `database::Connection::try_update_holidays`. Which provider is used is configuration, described
+//! by a `HolidaySource`. `rules::HolidayCalculator` works them out without the network.
+
+use std::fs;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::Duration;
+
+use chrono::{
+    DateTime,
+    Local,
+    NaiveDate,
+    NaiveDateTime,
+    Timelike,
+};
+
+use futures::future::BoxFuture;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::error::Result;
+
+pub mod canada_holidays;
+pub use canada_holidays::CanadaHolidays;
+
+pub mod ics;
+pub use ics::IcsFile;
+
+pub mod json;
+pub use json::JsonFile;
+
+pub mod rules;
+pub use rules::HolidayCalculator;
+
+pub trait HolidayProvider: Send + Sync {
+    /// Pure
+    ///
+    /// Identifies where the holidays come from, e.g., `canada-holidays.ca/BC`. Holidays are stored
+    /// with their source, so that changing it fetches them again.
+    fn source(&self) -> String;
+
+    /// Impure (reads from the network or filesystem, depending on the provider)
+    ///
+    /// Returns the holidays observed in `year`
+    /// # Errors
+    /// Returns an error if the holidays cannot be read or understood
+    fn holidays(&self, year: i32) -> BoxFuture<'_, Result<Vec<NaiveDate>>>;
+
+    /// Impure (reads the filesystem, depending on the provider)
+    ///
+    /// Returns whether holidays fetched at `fetched_at` may since have changed, so should be
+    /// fetched again, e.g., because the file they were read from has been modified. Holidays from
+    /// anywhere else are assumed not to change.
+    fn changed_since(&self, _fetched_at: NaiveDateTime) -> bool {
+        false
+    }
+}
+
+/// Impure (reads the filesystem)
+///
+/// Returns whether the file at `path` was modified after `time`, in local time. Times are compared
+/// to the second, as they are stored. A file that can't be read is counted as modified, so that
+/// reading it again reports why.
+fn modified_since(path: &Path, time: NaiveDateTime) -> bool {
+    !fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| {
+            let modified = DateTime::<Local>::from(modified).naive_local();
+            modified.with_nanosecond(0).unwrap_or(modified) <= time
+        })
+}
+
+/// Where the holidays stored for a year came from, returned by
+/// `database::Connection::holidays_fetched`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedHolidays {
+    pub year:       i32,
+    /// The `HolidayProvider::source` they were fetched from
+    pub source:     String,
+    pub fetched_at: NaiveDateTime,
+}
+
+/// How long to wait before trying to fetch holidays again after failing to. The wait doubles
+/// after each failure, up to a limit, so that being offline doesn't mean constant requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max:     Duration,
+    next:    Duration,
+}
+
+impl Backoff {
+    /// Pure
+    #[must_use] pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial,
+        }
+    }
+
+    /// Impure (modifies self)
+    ///
+    /// Returns how long to wait after another failure
+    pub fn failed(&mut self) -> Duration {
+        let wait = self.next;
+        self.next = (self.next * 2).min(self.max);
+        wait
+    }
+
+    /// Impure (modifies self)
+    ///
+    /// Starts again from the initial wait, after succeeding
+    pub fn succeeded(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+/// Which `HolidayProvider` to use, as it is configured
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HolidaySource {
+    /// <https://canada-holidays.ca>, for a province or territory given by its two-letter
+    /// abbreviation, e.g., `BC`
+    CanadaHolidays {
+        province: String,
+    },
+    /// An iCalendar file, e.g., exported from a calendar app
+    IcsFile {
+        path: PathBuf,
+    },
+    /// A JSON list of dates, as described in `json`
+    JsonFile {
+        path: PathBuf,
+    },
+    /// Calculated from the rules for a region, e.g., `CA-BC`, as described in `rules`. Needs no
+    /// network or files.
+    Rules {
+        region: String,
+    },
+}
+
+impl Default for HolidaySource {
+    fn default() -> Self {
+        Self::CanadaHolidays {
+            province: "BC".into(),
+        }
+    }
+}
+
+impl HolidaySource {
+    /// Pure
+    #[must_use] pub fn provider(&self) -> Box<dyn HolidayProvider> {
+        match self {
+            HolidaySource::CanadaHolidays { province } => Box::new(CanadaHolidays::new(province)),
+            HolidaySource::IcsFile { path } => Box::new(IcsFile::new(path.clone())),
+            HolidaySource::JsonFile { path } => Box::new(JsonFile::new(path.clone())),
+            HolidaySource::Rules { region } => Box::new(HolidayCalculator::new(region)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max_until_success() {
+        let mut backoff = Backoff::new(Duration::from_mins(1), Duration::from_mins(5));
+
+        let waits: Vec<u64> = (0..5).map(|_| backoff.failed().as_secs()).collect();
+        assert_eq!(waits, vec![60, 120, 240, 300, 300]);
+
+        backoff.succeeded();
+        assert_eq!(backoff.failed(), Duration::from_mins(1));
+    }
+}
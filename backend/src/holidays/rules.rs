@@ -0,0 +1,467 @@
+//! Holidays calculated from rules, so that they can be known without the network or any files.
+//!
+//! Each region has a list of `HolidayRule`s. A rule gives the date of a holiday in any year since
+//! it was first observed, and how it is observed when it falls on a weekend. Holidays that are declared one year at a time,
+//! e.g., for a coronation, aren't covered.
+//!
+//! Regions are named by their ISO 3166 code: `CA` for Canadian federal holidays, `CA-BC` and so
+//! on for the provinces and territories, `US` for US federal holidays, `GB` for England and
+//! Wales, and `DE` for German national holidays.
+
+use std::collections::BTreeSet;
+
+use chrono::{
+    NaiveDate,
+    Datelike,
+    Weekday,
+    Duration,
+};
+
+use futures::future::BoxFuture;
+
+use crate::{
+    HolidayProvider,
+    error::{
+        BeavorError,
+        Result,
+    },
+};
+
+/// When a holiday falls in a year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The same date every year
+    Fixed {
+        month: u32,
+        day:   u32,
+    },
+    /// The `n`th `weekday` of `month`. A negative `n` counts back from the end of the month, so -1
+    /// is the last.
+    NthWeekday {
+        month:   u32,
+        weekday: Weekday,
+        n:       i32,
+    },
+    /// The last `weekday` on or before a date, e.g., Victoria Day is the Monday on or before May 24
+    WeekdayOnOrBefore {
+        month:   u32,
+        day:     u32,
+        weekday: Weekday,
+    },
+    /// Some days after Easter Sunday, or before it if negative
+    Easter {
+        offset: i64,
+    },
+}
+
+impl Rule {
+    /// Pure
+    ///
+    /// Returns the date of the holiday in `year`, or `None` if there is no such date, e.g., a
+    /// fifth Monday in a month with only four
+    #[must_use] pub fn date(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            Rule::Fixed { month, day } => NaiveDate::from_ymd_opt(year, month, day),
+            Rule::NthWeekday { month, weekday, n } if n > 0 => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, u8::try_from(n).ok()?)
+            },
+            Rule::NthWeekday { month, weekday, n } => {
+                let first_of_next = if month == 12 {
+                    NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+                } else {
+                    NaiveDate::from_ymd_opt(year, month + 1, 1)?
+                };
+                let last = last_weekday_on_or_before(first_of_next.pred_opt()?, weekday);
+                let date = last - Duration::weeks(i64::from(-n - 1));
+                (date.month() == month).then_some(date)
+            },
+            Rule::WeekdayOnOrBefore { month, day, weekday } => {
+                Some(last_weekday_on_or_before(NaiveDate::from_ymd_opt(year, month, day)?, weekday))
+            },
+            Rule::Easter { offset } => Some(easter_sunday(year)? + Duration::days(offset)),
+        }
+    }
+}
+
+/// Which day a holiday is observed on when it falls on a weekend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observed {
+    /// On the day, even on a weekend
+    OnTheDay,
+    /// On the next weekday that isn't already a holiday, as in Canada and the UK. So when
+    /// Christmas is on a Saturday, it is observed on Monday and Boxing Day on Tuesday.
+    NextWeekday,
+    /// On the Friday before for a Saturday, and the Monday after for a Sunday, as in the US
+    NearestWeekday,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HolidayRule {
+    pub name:       &'static str,
+    pub rule:       Rule,
+    pub observed:   Observed,
+    /// The first year the holiday was observed, or `None` if it has been for as long as matters
+    pub first_year: Option<i32>,
+}
+
+impl HolidayRule {
+    const fn new(name: &'static str, rule: Rule, observed: Observed) -> Self {
+        Self { name, rule, observed, first_year: None }
+    }
+
+    /// The same holiday, but only from `year` on
+    const fn since(self, year: i32) -> Self {
+        Self { first_year: Some(year), ..self }
+    }
+
+    /// Pure
+    ///
+    /// Returns the date of the holiday in `year`, or `None` if there is no such date or the
+    /// holiday wasn't observed yet
+    #[must_use] pub fn date(self, year: i32) -> Option<NaiveDate> {
+        self.first_year
+            .is_none_or(|first_year| year >= first_year)
+            .then(|| self.rule.date(year))
+            .flatten()
+    }
+
+    const fn fixed(name: &'static str, month: u32, day: u32, observed: Observed) -> Self {
+        Self::new(name, Rule::Fixed { month, day }, observed)
+    }
+
+    const fn nth(name: &'static str, n: i32, weekday: Weekday, month: u32, observed: Observed) -> Self {
+        Self::new(name, Rule::NthWeekday { month, weekday, n }, observed)
+    }
+
+    const fn easter(name: &'static str, offset: i64) -> Self {
+        Self::new(name, Rule::Easter { offset }, Observed::OnTheDay)
+    }
+}
+
+use Observed::{
+    NextWeekday,
+    NearestWeekday,
+    OnTheDay,
+};
+use Weekday::{
+    Mon,
+    Thu,
+};
+
+// Canada. Holidays that fall on a weekend are observed on the next weekday
+const NEW_YEARS_DAY:         HolidayRule = HolidayRule::fixed("New Year's Day", 1, 1, NextWeekday);
+const FAMILY_DAY:            HolidayRule = HolidayRule::nth("Family Day", 3, Mon, 2, NextWeekday);
+const GOOD_FRIDAY:           HolidayRule = HolidayRule::easter("Good Friday", -2);
+const EASTER_MONDAY:         HolidayRule = HolidayRule::easter("Easter Monday", 1);
+const VICTORIA_DAY:          HolidayRule = HolidayRule::new("Victoria Day", Rule::WeekdayOnOrBefore { month: 5, day: 24, weekday: Mon }, NextWeekday);
+const SAINT_JEAN_BAPTISTE:   HolidayRule = HolidayRule::fixed("Saint-Jean-Baptiste Day", 6, 24, NextWeekday);
+const INDIGENOUS_PEOPLES:    HolidayRule = HolidayRule::fixed("National Indigenous Peoples Day", 6, 21, NextWeekday);
+const CANADA_DAY:            HolidayRule = HolidayRule::fixed("Canada Day", 7, 1, NextWeekday);
+const NUNAVUT_DAY:           HolidayRule = HolidayRule::fixed("Nunavut Day", 7, 9, NextWeekday);
+const CIVIC_HOLIDAY:         HolidayRule = HolidayRule::nth("Civic Holiday", 1, Mon, 8, NextWeekday);
+const DISCOVERY_DAY:         HolidayRule = HolidayRule::nth("Discovery Day", 3, Mon, 8, NextWeekday);
+const LABOUR_DAY:            HolidayRule = HolidayRule::nth("Labour Day", 1, Mon, 9, NextWeekday);
+const TRUTH_RECONCILIATION:  HolidayRule = HolidayRule::fixed("National Day for Truth and Reconciliation", 9, 30, NextWeekday).since(2021);
+const THANKSGIVING:          HolidayRule = HolidayRule::nth("Thanksgiving", 2, Mon, 10, NextWeekday);
+const REMEMBRANCE_DAY:       HolidayRule = HolidayRule::fixed("Remembrance Day", 11, 11, NextWeekday);
+const CHRISTMAS_DAY:         HolidayRule = HolidayRule::fixed("Christmas Day", 12, 25, NextWeekday);
+const BOXING_DAY:            HolidayRule = HolidayRule::fixed("Boxing Day", 12, 26, NextWeekday);
+
+// Holidays that the provinces and territories took up in different years
+const AB_FAMILY_DAY:           HolidayRule = FAMILY_DAY.since(1990);
+const BC_FAMILY_DAY:           HolidayRule = FAMILY_DAY.since(2013);
+const LOUIS_RIEL_DAY:          HolidayRule = HolidayRule::nth("Louis Riel Day", 3, Mon, 2, NextWeekday).since(2008);
+const NB_FAMILY_DAY:           HolidayRule = FAMILY_DAY.since(2018);
+const HERITAGE_DAY:            HolidayRule = HolidayRule::nth("Heritage Day", 3, Mon, 2, NextWeekday).since(2015);
+const ON_FAMILY_DAY:           HolidayRule = FAMILY_DAY.since(2008);
+const ISLANDER_DAY:            HolidayRule = HolidayRule::nth("Islander Day", 3, Mon, 2, NextWeekday).since(2009);
+const SK_FAMILY_DAY:           HolidayRule = FAMILY_DAY.since(2007);
+const YT_INDIGENOUS_PEOPLES:   HolidayRule = INDIGENOUS_PEOPLES.since(2017);
+const TRUTH_RECONCILIATION_22: HolidayRule = TRUTH_RECONCILIATION.since(2022);
+const TRUTH_RECONCILIATION_23: HolidayRule = TRUTH_RECONCILIATION.since(2023);
+
+const CANADA: &[HolidayRule] = &[NEW_YEARS_DAY, GOOD_FRIDAY, EASTER_MONDAY, VICTORIA_DAY, CANADA_DAY, LABOUR_DAY, TRUTH_RECONCILIATION, THANKSGIVING, REMEMBRANCE_DAY, CHRISTMAS_DAY, BOXING_DAY];
+const ALBERTA: &[HolidayRule] = &[NEW_YEARS_DAY, AB_FAMILY_DAY, GOOD_FRIDAY, VICTORIA_DAY, CANADA_DAY, LABOUR_DAY, THANKSGIVING, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const BRITISH_COLUMBIA: &[HolidayRule] = &[NEW_YEARS_DAY, BC_FAMILY_DAY, GOOD_FRIDAY, VICTORIA_DAY, CANADA_DAY, CIVIC_HOLIDAY, LABOUR_DAY, TRUTH_RECONCILIATION_23, THANKSGIVING, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const MANITOBA: &[HolidayRule] = &[NEW_YEARS_DAY, LOUIS_RIEL_DAY, GOOD_FRIDAY, VICTORIA_DAY, CANADA_DAY, LABOUR_DAY, TRUTH_RECONCILIATION_23, THANKSGIVING, CHRISTMAS_DAY];
+const NEW_BRUNSWICK: &[HolidayRule] = &[NEW_YEARS_DAY, NB_FAMILY_DAY, GOOD_FRIDAY, CANADA_DAY, CIVIC_HOLIDAY, LABOUR_DAY, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const NEWFOUNDLAND: &[HolidayRule] = &[NEW_YEARS_DAY, GOOD_FRIDAY, CANADA_DAY, LABOUR_DAY, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const NOVA_SCOTIA: &[HolidayRule] = &[NEW_YEARS_DAY, HERITAGE_DAY, GOOD_FRIDAY, CANADA_DAY, LABOUR_DAY, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const NORTHWEST_TERRITORIES: &[HolidayRule] = &[NEW_YEARS_DAY, GOOD_FRIDAY, VICTORIA_DAY, INDIGENOUS_PEOPLES, CANADA_DAY, CIVIC_HOLIDAY, LABOUR_DAY, TRUTH_RECONCILIATION_22, THANKSGIVING, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const NUNAVUT: &[HolidayRule] = &[NEW_YEARS_DAY, GOOD_FRIDAY, VICTORIA_DAY, CANADA_DAY, NUNAVUT_DAY, CIVIC_HOLIDAY, LABOUR_DAY, TRUTH_RECONCILIATION_22, THANKSGIVING, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const ONTARIO: &[HolidayRule] = &[NEW_YEARS_DAY, ON_FAMILY_DAY, GOOD_FRIDAY, VICTORIA_DAY, CANADA_DAY, LABOUR_DAY, THANKSGIVING, CHRISTMAS_DAY, BOXING_DAY];
+const PRINCE_EDWARD_ISLAND: &[HolidayRule] = &[NEW_YEARS_DAY, ISLANDER_DAY, GOOD_FRIDAY, CANADA_DAY, LABOUR_DAY, TRUTH_RECONCILIATION_22, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const QUEBEC: &[HolidayRule] = &[NEW_YEARS_DAY, GOOD_FRIDAY, VICTORIA_DAY, SAINT_JEAN_BAPTISTE, CANADA_DAY, LABOUR_DAY, THANKSGIVING, CHRISTMAS_DAY];
+const SASKATCHEWAN: &[HolidayRule] = &[NEW_YEARS_DAY, SK_FAMILY_DAY, GOOD_FRIDAY, VICTORIA_DAY, CANADA_DAY, CIVIC_HOLIDAY, LABOUR_DAY, THANKSGIVING, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+const YUKON: &[HolidayRule] = &[NEW_YEARS_DAY, GOOD_FRIDAY, VICTORIA_DAY, YT_INDIGENOUS_PEOPLES, CANADA_DAY, DISCOVERY_DAY, LABOUR_DAY, TRUTH_RECONCILIATION_23, THANKSGIVING, REMEMBRANCE_DAY, CHRISTMAS_DAY];
+
+const UNITED_STATES: &[HolidayRule] = &[
+    HolidayRule::fixed("New Year's Day", 1, 1, NearestWeekday),
+    HolidayRule::nth("Martin Luther King Jr. Day", 3, Mon, 1, NearestWeekday).since(1986),
+    HolidayRule::nth("Washington's Birthday", 3, Mon, 2, NearestWeekday),
+    HolidayRule::nth("Memorial Day", -1, Mon, 5, NearestWeekday),
+    HolidayRule::fixed("Juneteenth", 6, 19, NearestWeekday).since(2021),
+    HolidayRule::fixed("Independence Day", 7, 4, NearestWeekday),
+    HolidayRule::nth("Labor Day", 1, Mon, 9, NearestWeekday),
+    HolidayRule::nth("Columbus Day", 2, Mon, 10, NearestWeekday),
+    HolidayRule::fixed("Veterans Day", 11, 11, NearestWeekday),
+    HolidayRule::nth("Thanksgiving Day", 4, Thu, 11, NearestWeekday),
+    HolidayRule::fixed("Christmas Day", 12, 25, NearestWeekday),
+];
+
+const ENGLAND_AND_WALES: &[HolidayRule] = &[
+    HolidayRule::fixed("New Year's Day", 1, 1, NextWeekday),
+    GOOD_FRIDAY,
+    EASTER_MONDAY,
+    HolidayRule::nth("Early May bank holiday", 1, Mon, 5, NextWeekday),
+    HolidayRule::nth("Spring bank holiday", -1, Mon, 5, NextWeekday),
+    HolidayRule::nth("Summer bank holiday", -1, Mon, 8, NextWeekday),
+    HolidayRule::fixed("Christmas Day", 12, 25, NextWeekday),
+    HolidayRule::fixed("Boxing Day", 12, 26, NextWeekday),
+];
+
+const GERMANY: &[HolidayRule] = &[
+    HolidayRule::fixed("Neujahr", 1, 1, OnTheDay),
+    HolidayRule::easter("Karfreitag", -2),
+    HolidayRule::easter("Ostermontag", 1),
+    HolidayRule::fixed("Tag der Arbeit", 5, 1, OnTheDay),
+    HolidayRule::easter("Christi Himmelfahrt", 39),
+    HolidayRule::easter("Pfingstmontag", 50),
+    HolidayRule::fixed("Tag der Deutschen Einheit", 10, 3, OnTheDay).since(1990),
+    HolidayRule::fixed("Erster Weihnachtstag", 12, 25, OnTheDay),
+    HolidayRule::fixed("Zweiter Weihnachtstag", 12, 26, OnTheDay),
+];
+
+/// Pure
+///
+/// Returns the rules for a region, given by its code as described in the module documentation,
+/// or `None` if there are none
+#[must_use] pub fn rules(region: &str) -> Option<&'static [HolidayRule]> {
+    Some(match region.to_uppercase().as_str() {
+        "CA"    => CANADA,
+        "CA-AB" => ALBERTA,
+        "CA-BC" => BRITISH_COLUMBIA,
+        "CA-MB" => MANITOBA,
+        "CA-NB" => NEW_BRUNSWICK,
+        "CA-NL" => NEWFOUNDLAND,
+        "CA-NS" => NOVA_SCOTIA,
+        "CA-NT" => NORTHWEST_TERRITORIES,
+        "CA-NU" => NUNAVUT,
+        "CA-ON" => ONTARIO,
+        "CA-PE" => PRINCE_EDWARD_ISLAND,
+        "CA-QC" => QUEBEC,
+        "CA-SK" => SASKATCHEWAN,
+        "CA-YT" => YUKON,
+        "US"    => UNITED_STATES,
+        "GB"    => ENGLAND_AND_WALES,
+        "DE"    => GERMANY,
+        _ => return None,
+    })
+}
+
+/// Pure
+///
+/// Returns the days the holidays given by `rules` are observed on in `year`, in order. A holiday
+/// near the turn of the year may be observed in the year before or after it falls, e.g., in the
+/// US, New Year's Day 2022 was observed on December 31, 2021.
+#[must_use] pub fn observed_holidays(rules: &[HolidayRule], year: i32) -> Vec<NaiveDate> {
+    let mut holidays: Vec<(NaiveDate, Observed)> = (year - 1..=year + 1)
+        .flat_map(|y| rules.iter().filter_map(move |r| Some((r.date(y)?, r.observed))))
+        .collect();
+    holidays.sort_by_key(|&(date, _)| date);
+
+    let mut observed = BTreeSet::new();
+    for (date, observance) in holidays {
+        let day = match observance {
+            OnTheDay => date,
+            NearestWeekday => match date.weekday() {
+                Weekday::Sat => date - Duration::days(1),
+                Weekday::Sun => date + Duration::days(1),
+                _ => date,
+            },
+            NextWeekday => {
+                let mut day = date;
+                while is_weekend(day) || observed.contains(&day) {
+                    day += Duration::days(1);
+                }
+                day
+            },
+        };
+        observed.insert(day);
+    }
+
+    observed.into_iter().filter(|d| d.year() == year).collect()
+}
+
+/// Pure
+///
+/// Returns the date of Easter Sunday in the Gregorian calendar, by the anonymous Gregorian
+/// algorithm (Meeus/Jones/Butcher). The names follow the algorithm as it is usually written.
+#[allow(clippy::many_single_char_names)]
+#[must_use] pub fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year.rem_euclid(19);
+    let b = year.div_euclid(100);
+    let c = year.rem_euclid(100);
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month.try_into().ok()?, day.try_into().ok()?)
+}
+
+fn last_weekday_on_or_before(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_back = (7 + date.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    date - Duration::days(days_back.into())
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// Calculates the holidays of a region from its rules, without the network
+#[derive(Debug, Clone)]
+pub struct HolidayCalculator {
+    region: String,
+}
+
+impl HolidayCalculator {
+    /// Pure
+    ///
+    /// `region` is a code as described in the module documentation, e.g., `CA-BC`
+    #[must_use] pub fn new(region: &str) -> Self {
+        Self {
+            region: region.to_uppercase(),
+        }
+    }
+}
+
+impl HolidayProvider for HolidayCalculator {
+    fn source(&self) -> String {
+        format!("rules/{}", self.region)
+    }
+
+    fn holidays(&self, year: i32) -> BoxFuture<'_, Result<Vec<NaiveDate>>> {
+        let holidays = rules(&self.region)
+            .map(|rules| observed_holidays(rules, year))
+            .ok_or_else(|| BeavorError::NotFound(format!("holiday rules for {}", self.region)));
+
+        Box::pin(async move { holidays })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dates(year: i32, month_days: &[(u32, u32)]) -> Vec<NaiveDate> {
+        month_days.iter().map(|&(m, d)| NaiveDate::from_ymd_opt(year, m, d).unwrap()).collect()
+    }
+
+    fn observed(region: &str, year: i32) -> Vec<NaiveDate> {
+        observed_holidays(rules(region).unwrap(), year)
+    }
+
+    #[test]
+    fn test_easter_sunday() {
+        for (year, month, day) in [(1961, 4, 2), (2019, 4, 21), (2024, 3, 31), (2025, 4, 20), (2038, 4, 25), (2285, 3, 22)] {
+            assert_eq!(easter_sunday(year), NaiveDate::from_ymd_opt(year, month, day), "Easter {year}");
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+        assert_eq!(Rule::NthWeekday { month: 5, weekday: Mon, n: -1 }.date(2024), date(2024, 5, 27));
+        assert_eq!(Rule::NthWeekday { month: 12, weekday: Mon, n: -2 }.date(2024), date(2024, 12, 23));
+        assert_eq!(Rule::NthWeekday { month: 9, weekday: Mon, n: 5 }.date(2024), date(2024, 9, 30));
+        assert_eq!(Rule::NthWeekday { month: 10, weekday: Mon, n: 5 }.date(2024), None);
+        assert_eq!(Rule::NthWeekday { month: 10, weekday: Mon, n: -5 }.date(2024), None);
+        // May 24 itself is a Monday in 2021, but a Friday in 2024
+        assert_eq!(VICTORIA_DAY.rule.date(2021), date(2021, 5, 24));
+        assert_eq!(VICTORIA_DAY.rule.date(2024), date(2024, 5, 20));
+        assert_eq!(Rule::Fixed { month: 2, day: 29 }.date(2023), None);
+    }
+
+    #[test]
+    fn test_british_columbia() {
+        assert_eq!(observed("CA-BC", 2024), dates(2024, &[(1, 1), (2, 19), (3, 29), (5, 20), (7, 1), (8, 5), (9, 2), (9, 30), (10, 14), (11, 11), (12, 25)]));
+    }
+
+    #[test]
+    fn test_ontario_weekend_holidays_are_observed_on_following_weekdays() {
+        // New Year's Day is a Saturday, and Christmas a Sunday, so Boxing Day moves to Tuesday
+        assert_eq!(observed("CA-ON", 2022), dates(2022, &[(1, 3), (2, 21), (4, 15), (5, 23), (7, 1), (9, 5), (10, 10), (12, 26), (12, 27)]));
+    }
+
+    #[test]
+    fn test_quebec() {
+        assert_eq!(observed("ca-qc", 2024), dates(2024, &[(1, 1), (3, 29), (5, 20), (6, 24), (7, 1), (9, 2), (10, 14), (12, 25)]));
+    }
+
+    #[test]
+    fn test_canada() {
+        assert_eq!(observed("CA", 2024), dates(2024, &[(1, 1), (3, 29), (4, 1), (5, 20), (7, 1), (9, 2), (9, 30), (10, 14), (11, 11), (12, 25), (12, 26)]));
+    }
+
+    #[test]
+    fn test_united_states_observes_nearest_weekday() {
+        // New Year's Day 2022 is a Saturday, so it's observed on the last day of 2021
+        assert_eq!(observed("US", 2021), dates(2021, &[(1, 1), (1, 18), (2, 15), (5, 31), (6, 18), (7, 5), (9, 6), (10, 11), (11, 11), (11, 25), (12, 24), (12, 31)]));
+        assert_eq!(observed("US", 2022)[0], NaiveDate::from_ymd_opt(2022, 1, 17).unwrap());
+    }
+
+    #[test]
+    fn test_england_and_wales() {
+        assert_eq!(observed("GB", 2021), dates(2021, &[(1, 1), (4, 2), (4, 5), (5, 3), (5, 31), (8, 30), (12, 27), (12, 28)]));
+    }
+
+    #[test]
+    fn test_germany_observes_holidays_on_the_day() {
+        assert_eq!(observed("DE", 2024), dates(2024, &[(1, 1), (3, 29), (4, 1), (5, 1), (5, 9), (5, 20), (10, 3), (12, 25), (12, 26)]));
+        // Christmas 2022 is a Sunday
+        assert!(observed("DE", 2022).contains(&NaiveDate::from_ymd_opt(2022, 12, 25).unwrap()));
+    }
+
+    #[test]
+    fn test_holidays_are_only_observed_from_their_first_year() {
+        let truth_and_reconciliation = |year| NaiveDate::from_ymd_opt(year, 9, 30).unwrap();
+        assert!(!observed("CA", 2019).contains(&truth_and_reconciliation(2019)));
+        assert!(observed("CA", 2021).contains(&truth_and_reconciliation(2021)));
+        // British Columbia made it a stat holiday later
+        assert!(!observed("CA-BC", 2022).contains(&truth_and_reconciliation(2022)));
+        assert!(observed("CA-BC", 2023).contains(&NaiveDate::from_ymd_opt(2023, 10, 2).unwrap()));
+
+        assert_eq!(observed("CA-BC", 2012).len(), observed("CA-BC", 2013).len() - 1);
+        assert!(!observed("US", 2020).contains(&NaiveDate::from_ymd_opt(2020, 6, 19).unwrap()));
+    }
+
+    #[test]
+    fn test_every_region_has_holidays_every_year() {
+        for region in ["CA", "CA-AB", "CA-BC", "CA-MB", "CA-NB", "CA-NL", "CA-NS", "CA-NT", "CA-NU", "CA-ON", "CA-PE", "CA-QC", "CA-SK", "CA-YT", "US", "GB", "DE"] {
+            for year in 2000..2100 {
+                let holidays = observed(region, year);
+                let in_effect = rules(region).unwrap().iter().filter(|r| r.first_year.is_none_or(|first| year >= first)).count();
+                assert!(holidays.len() >= in_effect - 1, "{region} {year}");
+                assert!(holidays.iter().all(|d| d.year() == year), "{region} {year}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calculator() {
+        let calculator = HolidayCalculator::new("ca-bc");
+        assert_eq!(calculator.source(), "rules/CA-BC");
+        assert_eq!(calculator.holidays(2024).await.unwrap(), observed("CA-BC", 2024));
+
+        assert!(matches!(HolidayCalculator::new("XX").holidays(2024).await, Err(BeavorError::NotFound(_))));
+    }
+}
@@ -1,5 +1,17 @@
 pub mod database;
 pub use database::Connection as DatabaseManager;
+pub use database::{ArchiveFilter, TrashedTask};
+
+pub mod migrations;
+
+pub mod changes;
+pub use changes::ChangeDetector;
+
+pub mod backup;
+pub use backup::{Backup, BackupPolicy};
+
+pub mod error;
+pub use error::BeavorError;
 
 pub mod due_date;
 pub use due_date::DueDate;
@@ -9,8 +21,25 @@ pub use task::{Task, Hyperlink};
 
 pub mod utils;
 
+pub mod holidays;
+pub use holidays::{HolidayProvider, HolidaySource, FetchedHolidays};
+
 pub mod schedule;
 pub use schedule::Schedule;
 
+pub mod days_off;
+pub use days_off::DayOff;
+
+pub mod report;
+
+pub mod history;
+pub use history::TaskChange;
+
+pub mod undo;
+pub use undo::Edit;
+
+#[cfg(test)]
+mod fixtures;
+
 pub mod time_logger;
 pub use time_logger::TimeSheet;
@@ -0,0 +1,298 @@
+//! Schema versioning for beavor databases.
+//!
+//! The version of a database is stored in its `user_version` pragma. `resources/schema.sql`
+//! describes version 1 (the v1.1 schema); every later change to the schema is an upgrade script
+//! in `resources/upgrade/` registered in `MIGRATIONS`, and is applied at runtime by
+//! `database::Connection::new`.
+//!
+//! NOTE This file is also compiled into `build.rs` to create the database that the `query!`
+//! macros check against, so it must not depend on anything else in this crate.
+
+use core::fmt::Display;
+
+use sqlx::{
+    Row,
+    Connection,
+    sqlite::SqliteConnection,
+};
+
+/// An upgrade script that takes a database from `version - 1` to `version`
+pub struct Migration {
+    pub version: i64,
+    pub name:    &'static str,
+    pub sql:     &'static str,
+}
+
+/// All upgrade scripts, in the order they must be applied
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name:    "v1.1",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.1.sql"),
+    },
+    Migration {
+        version: 2,
+        name:    "v1.2",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.2.sql"),
+    },
+    Migration {
+        version: 3,
+        name:    "v1.3",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.3.sql"),
+    },
+    Migration {
+        version: 4,
+        name:    "v1.4",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.4.sql"),
+    },
+    Migration {
+        version: 5,
+        name:    "v1.5",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.5.sql"),
+    },
+    Migration {
+        version: 6,
+        name:    "v1.6",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.6.sql"),
+    },
+    Migration {
+        version: 7,
+        name:    "v1.7",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.7.sql"),
+    },
+    Migration {
+        version: 8,
+        name:    "v1.8",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.8.sql"),
+    },
+    Migration {
+        version: 9,
+        name:    "v1.9",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.9.sql"),
+    },
+    Migration {
+        version: 10,
+        name:    "v1.10",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.10.sql"),
+    },
+    Migration {
+        version: 11,
+        name:    "v1.11",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.11.sql"),
+    },
+    Migration {
+        version: 12,
+        name:    "v1.12",
+        sql:     include_str!("../resources/upgrade/upgrade_v1.12.sql"),
+    },
+];
+
+/// The schema version of `resources/schema.sql`
+const BASELINE_VERSION: i64 = 1;
+
+/// Pure
+///
+/// The schema version that this build of beavor reads and writes
+#[must_use] pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The database was written by a newer version of beavor than this one
+    NewerSchema {
+        found:     i64,
+        supported: i64,
+    },
+    /// An upgrade script failed to apply
+    Script {
+        name:   &'static str,
+        source: sqlx::Error,
+    },
+    Sqlx(sqlx::Error),
+}
+
+impl Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationError::NewerSchema { found, supported } => write!(
+                f,
+                "The database has schema version {found}, but this version of beavor only supports up to version {supported}. Please upgrade beavor to open it."
+            ),
+            MigrationError::Script { name, source } => write!(f, "Failed to upgrade database to {name}: {source}"),
+            MigrationError::Sqlx(e) => write!(f, "Failed to migrate database: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MigrationError::NewerSchema {..} => None,
+            MigrationError::Script { source, .. } => Some(source),
+            MigrationError::Sqlx(e) => Some(e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for MigrationError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Sqlx(value)
+    }
+}
+
+/// Impure (reads database)
+///
+/// Returns the schema version of the database.
+/// Databases created before versions were recorded have a `user_version` of 0, so these are
+/// identified by their tables instead.
+///
+/// # Errors
+/// Returns an error if the database cannot be read
+pub async fn current_version(conn: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    let user_version: i64 = sqlx::query("PRAGMA user_version")
+        .fetch_one(&mut *conn)
+        .await?
+        .get(0);
+
+    if user_version != 0 {
+        return Ok(user_version);
+    }
+
+    // Databases created from `schema.sql` by the old Makefile or `with_new_database` have
+    // hyperlinks but no recorded version
+    let has_hyperlinks = sqlx::query("
+        SELECT name
+        FROM sqlite_master
+        WHERE type == 'table' AND name == 'hyperlinks'
+    ")
+        .fetch_optional(&mut *conn)
+        .await?
+        .is_some();
+
+    Ok(if has_hyperlinks {BASELINE_VERSION} else {0})
+}
+
+/// Impure (writes database)
+///
+/// Applies every upgrade script newer than the database's version, in order, inside a single
+/// transaction. If any script fails, the database is left untouched.
+///
+/// Foreign keys should be disabled on `conn`, otherwise rebuilding a table will cascade deletes
+/// into the tables that reference it.
+///
+/// # Errors
+/// Returns an error if the database is newer than this build supports, or if any script fails.
+pub async fn migrate(conn: &mut SqliteConnection) -> Result<(), MigrationError> {
+    let version = current_version(conn).await?;
+
+    if version > latest_version() {
+        return Err(MigrationError::NewerSchema {
+            found:     version,
+            supported: latest_version(),
+        });
+    }
+
+    let mut tx = conn.begin().await?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        sqlx::query(migration.sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(|source| MigrationError::Script {
+                name: migration.name,
+                source,
+            })?;
+    }
+
+    // PRAGMA does not accept bound parameters
+    sqlx::query(&format!("PRAGMA user_version = {}", latest_version()))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use sqlx::{
+        ConnectOptions,
+        sqlite::SqliteConnectOptions,
+    };
+
+    use super::*;
+
+    async fn empty_database() -> SqliteConnection {
+        SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .foreign_keys(false)
+            .connect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_database() {
+        let mut conn = empty_database().await;
+
+        sqlx::query("
+            CREATE TABLE tasks(Category TEXT, Finished BOOLEAN, Name TEXT, Budget INTEGER, Time INTEGER, Used INTEGER, NextAction TEXT, DueDate TEXT, Notes TEXT, DateAdded TEXT);
+            CREATE TABLE days_off(Day TEXT UNIQUE, Reason TEXT CHECK(Reason IN ('vacation', 'stat_holiday', 'travel')));
+            INSERT INTO tasks VALUES('Work', false, 'Legacy task', 60, 60, 0, '2024-01-01', 'ASAP', '', '2024-01-01');
+        ")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(current_version(&mut conn).await.unwrap(), 0);
+
+        migrate(&mut conn).await.unwrap();
+
+        assert_eq!(current_version(&mut conn).await.unwrap(), latest_version());
+        let row = sqlx::query("SELECT Name, Time, DueKind, DueOn FROM tasks WHERE TaskID == 1")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, usize>(0), "Legacy task");
+        // Legacy times are in minutes
+        assert_eq!(row.get::<i64, usize>(1), 3600);
+        assert_eq!(row.get::<i64, usize>(2), 0);
+        assert_eq!(row.get::<Option<String>, usize>(3), None);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_is_idempotent() {
+        let mut conn = empty_database().await;
+
+        sqlx::query(include_str!("../resources/schema.sql"))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(current_version(&mut conn).await.unwrap(), BASELINE_VERSION);
+
+        migrate(&mut conn).await.unwrap();
+        migrate(&mut conn).await.unwrap();
+
+        assert_eq!(current_version(&mut conn).await.unwrap(), latest_version());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_rejects_newer_database() {
+        let mut conn = empty_database().await;
+
+        sqlx::query(&format!("PRAGMA user_version = {}", latest_version() + 1))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            migrate(&mut conn).await,
+            Err(MigrationError::NewerSchema { found, supported }) if found == latest_version() + 1 && supported == latest_version()
+        ));
+    }
+}
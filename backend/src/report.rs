@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use chrono::{
+    NaiveDate,
+    Duration,
+    Weekday,
+};
+
+use crate::Task;
+
+/// Pure
+///
+/// Counts the tasks finished in each week, keyed by the Monday that starts the week.
+/// Tasks with no `finished_date` are not counted.
+/// Typically called with the result of `Connection::finished_tasks`.
+#[must_use] pub fn throughput_per_week(tasks: &[Task]) -> BTreeMap<NaiveDate, usize> {
+    let mut weeks = BTreeMap::new();
+
+    for finished_date in tasks.iter().filter_map(|t| t.finished_date) {
+        *weeks.entry(finished_date.week(Weekday::Mon).first_day()).or_insert(0) += 1;
+    }
+
+    weeks
+}
+
+/// Pure
+///
+/// Returns the mean time from adding to finishing a task, or `None` if no task has a
+/// `finished_date`
+#[must_use] pub fn average_lead_time(tasks: &[Task]) -> Option<Duration> {
+    let lead_times: Vec<Duration> = tasks.iter().filter_map(Task::lead_time).collect();
+
+    let count = i32::try_from(lead_times.len()).ok().filter(|n| *n > 0)?;
+
+    Some(lead_times.into_iter().sum::<Duration>() / count)
+}
+
+#[cfg(test)]
+#[allow(clippy::zero_prefixed_literal)]
+mod tests {
+    use super::*;
+
+    fn finished(added: NaiveDate, finished: Option<NaiveDate>) -> Task {
+        Task {
+            finished: true,
+            date_added: added,
+            finished_date: finished,
+            ..Task::default()
+        }
+    }
+
+    #[test]
+    fn test_throughput_per_week() {
+        let d = |day| NaiveDate::from_ymd_opt(2024,01,day).unwrap();
+
+        let tasks = [
+            finished(d(01), Some(d(01))), // Monday
+            finished(d(01), Some(d(07))), // Sunday of the same week
+            finished(d(01), Some(d(08))), // Next Monday
+            finished(d(01), None),
+        ];
+
+        assert_eq!(
+            throughput_per_week(&tasks),
+            BTreeMap::from([(d(01), 2), (d(08), 1)])
+        );
+    }
+
+    #[test]
+    fn test_average_lead_time() {
+        let d = |day| NaiveDate::from_ymd_opt(2024,01,day).unwrap();
+
+        assert_eq!(average_lead_time(&[]), None);
+        assert_eq!(average_lead_time(&[finished(d(01), None)]), None);
+
+        assert_eq!(
+            average_lead_time(&[
+                finished(d(01), Some(d(02))),
+                finished(d(01), Some(d(04))),
+                finished(d(01), None),
+            ]),
+            Some(Duration::days(2))
+        );
+    }
+}
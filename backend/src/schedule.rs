@@ -1,4 +1,5 @@
 use std::cmp::{max, min};
+use std::ops::RangeInclusive;
 
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +15,7 @@ use crate::{
     Task,
     task::Id,
     DueDate,
+    DayOff,
     utils::{
         today_date,
         now_time,
@@ -22,6 +24,18 @@ use crate::{
 
 use std::collections::HashMap;
 
+mod strategy;
+pub use strategy::{
+    SchedulingStrategy,
+    Strategy,
+    EarliestDueFirst,
+    JustInTime,
+    EvenSpread,
+};
+
+mod feasibility;
+pub use feasibility::Feasibility;
+
 // NOTE This stores more state than necessary but I don't feel like optimizing it and I doubt it'll be a bottleneck anytime soon
 pub struct DateIterator {
     prev: NaiveDate,
@@ -127,26 +141,71 @@ impl WorkDay {
 
 #[derive(Clone, Default, Debug)]
 pub struct Schedule  {
-    days_off: Vec<NaiveDate>,
+    days_off: HashMap<NaiveDate, DayOff>,
     work_days: WorkDays,
     work_week: WorkWeek,
+    feasibility: Feasibility,
 }
 
 impl Schedule {
     /// Construct a `Schedule` with the passed `days_off` and `work_week`
-    /// Calculates workloads in-place from `tasks`
-    #[must_use] pub fn new (days_off: Vec<NaiveDate>, tasks: &Vec<Task>, work_week: WorkWeek) -> Self {
+    /// Calculates workloads in-place from `tasks`, with the default `Strategy`
+    #[must_use] pub fn new (days_off: Vec<DayOff>, tasks: &[Task], work_week: WorkWeek) -> Self {
+        Self::with_strategy(days_off, tasks, work_week, &Strategy::default())
+    }
+
+    /// Construct a `Schedule` with the passed `days_off` and `work_week`
+    /// Calculates workloads in-place from `tasks`, spreading them over days with `strategy`
+    #[must_use] pub fn with_strategy (days_off: Vec<DayOff>, tasks: &[Task], work_week: WorkWeek, strategy: &dyn SchedulingStrategy) -> Self {
         let mut schedule = Schedule {
-            days_off,
+            days_off: days_off.into_iter().map(|d| (d.date, d)).collect(),
             work_days: WorkDays::new(),
             work_week,
+            feasibility: Feasibility::default(),
         };
 
-        schedule.assign_time_to_days(tasks);
+        schedule.feasibility.unschedulable = tasks
+            .iter()
+            .filter(|task| task.time_remaining() > Duration::zero())
+            .filter(|task| schedule.work_days_for_task(task).is_some_and(|days| days.is_empty()))
+            .map(|task| task.id)
+            .collect();
+
+        strategy.assign(&mut schedule, tasks);
 
         schedule
     }
 
+    /// Pure
+    ///
+    /// Returns the work that could not be fit in the working hours before it is due
+    #[must_use] pub fn feasibility(&self) -> &Feasibility {
+        &self.feasibility
+    }
+
+    /// Impure (calls `today_date`)
+    ///
+    /// Returns the years that a schedule of `tasks` could assign work to, i.e., from this year to
+    /// the year of the latest due or next action date. Holidays are needed for all of them.
+    #[must_use] pub fn horizon(tasks: &[Task]) -> RangeInclusive<i32> {
+        Self::horizon_from(tasks, today_date())
+    }
+
+    /// Pure
+    ///
+    /// Pure version of `horizon`
+    #[must_use] fn horizon_from(tasks: &[Task], today: NaiveDate) -> RangeInclusive<i32> {
+        let last = tasks
+            .iter()
+            .flat_map(|task| match task.due_date {
+                DueDate::Date(due_date) => vec![task.next_action_date, due_date],
+                DueDate::Asap | DueDate::Never => vec![task.next_action_date],
+            })
+            .fold(today, max);
+
+        today.year()..=last.year()
+    }
+
     /// Impure (calls `today_date` and `now_time`)
     ///
     /// Return the amount of time left to work today
@@ -155,7 +214,7 @@ impl Schedule {
     /// If there are no hours of work today, return None
     #[must_use] pub fn time_remaining_today(&self) -> Option<Duration> {
         Self::time_remaining_of_hours(
-            &self.work_week.days[&today_date().weekday()],
+            &self.working_hours_on(today_date()),
             now_time()
         )
     }
@@ -165,12 +224,19 @@ impl Schedule {
     /// Calculates how much time remains in a `WorkingHours` at a given time.
     /// Used for `time_remaining_today`.
     #[must_use] fn time_remaining_of_hours (day: &WorkingHours, time: NaiveTime) -> Option<Duration>{
-        day
+        if day.hours_of_work.is_empty() {
+            return None;
+        }
+
+        Some(day
             .hours_of_work
+            .iter()
             .map(|hour_range| max(
                 Duration::zero(),
                 hour_range.end - max(time, hour_range.start)
             ))
+            .sum()
+        )
     }
 
     /// Impure (calls `time_remaining_today`, `today_date`)
@@ -207,9 +273,9 @@ impl Schedule {
 
     /// Impure (calls `last_available_date_for_task`, `first_available_date_for_task`)
     ///
-    /// Returns an iterator over the days a task can be worked on, or nothing if the task has no due
+    /// Returns the days a task can be worked on, in order, or nothing if the task has no due
     /// date (i.e., the range is undefined)
-    fn work_days_for_task(&self, task: &Task) -> Option<Vec<NaiveDate>> {
+    #[must_use] pub fn work_days_for_task(&self, task: &Task) -> Option<Vec<NaiveDate>> {
         self.last_available_date_for_task(task)
             .map(|due_date| self.work_days_from(self.first_available_date_for_task(task), due_date).collect())
     }
@@ -268,62 +334,28 @@ impl Schedule {
 
     /// Impure (modifies self)
     ///
-    /// Calculates and records the number of minutes that need to be worked each day
-    fn assign_time_to_days (&mut self, tasks: &Vec<Task>) {
-        self.assign_time_by_frontloading_work(tasks);
+    /// Assigns `duration` of work on `task` to a day, for a `SchedulingStrategy`
+    ///
+    /// # Panics
+    /// Panics if passed a negative `Duration` of time
+    pub fn assign(&mut self, day: NaiveDate, task: &Task, duration: Duration) {
+        let working_hours = self.working_hours_on(day);
+        self.work_days
+            .entry(day)
+            .or_insert(WorkDay::new(working_hours))
+            .add(task, duration);
     }
 
     /// Impure (modifies self)
     ///
-    /// One variant of the workload calculation
-    /// This sorts the tasks from first due to last, and schedules work as early as possible
-    // TODO a lot of this code counts on `Duration`s being positive, but the chrono `Duration` doesn't make this guarantee
-    fn assign_time_by_frontloading_work (&mut self, tasks: &Vec<Task>) {
-
-        // Sort from first to last due
-        let mut sorted_tasks = tasks.clone();
-        sorted_tasks.sort_unstable_by(|t1, t2| t1.due_date.cmp(&t2.due_date));
-
-        for task in tasks {
-            // Track the time that has not yet been assigned to a day
-            let mut time_to_assign = task.time_remaining();
-
-            if let Some(task_days) = self.work_days_for_task(task) {
-                for day in task_days {
-                    if time_to_assign.num_seconds() == 0 {
-                        break; // Don't continue looping once all time is assigned
-                    }
-                    
-                    // Find how much time can be allocated to this day from this task
-                    let workload_for_day = min(
-                        self.time_available_on_date(day)
-                            .expect("This will be Some because all work days have non-None time, and this loops over work days only"),
-                        time_to_assign
-                    );
-                    // Remove the time to be allocated from the remaining time for the task
-                    time_to_assign = time_to_assign - workload_for_day;
-
-                    self.work_days
-                        .entry(day)
-                        .or_insert(WorkDay::new(WorkingHours::new(self.work_week.working_hours_on_day(day).hours_of_work)))
-                        .add(task, workload_for_day);
-                }
-
-                // If time remains, assign to final day
-                if time_to_assign.num_seconds() != 0 {
-                    self.work_days.get_mut(
-                            self.work_days_for_task(task)
-                                .expect("We already checked this is not None")
-                                .last()
-                                .expect("work_days_for_task never returns an empty iterator")
-                        )
-                        .expect("This will not be None because we've already inserted a value in the previous loop")
-                        .add(task, time_to_assign);
-                }
-            } else {
-                // TODO TBD how to handle tasks that are not available for any days
-            }
-        }
+    /// Assigns `duration` of work on `task` to a day that has no time left for it, and records
+    /// it in the `Feasibility` of the schedule
+    ///
+    /// # Panics
+    /// Panics if passed a negative `Duration` of time
+    pub fn assign_over_capacity(&mut self, day: NaiveDate, task: &Task, duration: Duration) {
+        self.assign(day, task, duration);
+        self.feasibility.over_capacity(day, task.id, duration);
     }
 
     /// Impure (calls `last_available_date_for_task`)
@@ -362,7 +394,26 @@ impl Schedule {
     ///
     /// Returns a boolean representing whether a given date is a work day.
     #[must_use] pub fn is_work_day(&self, date: NaiveDate) -> bool {
-        !self.days_off.contains(&date) && self.work_week.workdays().contains(&date.weekday())
+        !self.days_off.get(&date).is_some_and(DayOff::is_whole_day)
+            && self.working_hours_on(date).working_time() > Duration::zero()
+    }
+
+    /// Pure
+    ///
+    /// Returns the day off on a date, if it is wholly or partly off
+    #[must_use] pub fn day_off(&self, date: NaiveDate) -> Option<&DayOff> {
+        self.days_off.get(&date)
+    }
+
+    /// Pure
+    ///
+    /// Returns the hours of work on a date, less any part of it that is off
+    #[must_use] pub fn working_hours_on(&self, date: NaiveDate) -> WorkingHours {
+        let hours = self.work_week.working_hours_on_day(date);
+        match self.days_off.get(&date).and_then(|d| d.hours) {
+            Some(off) => hours.without(off),
+            None => hours,
+        }
     }
 
     /// Pure
@@ -377,7 +428,7 @@ impl Schedule {
                 .unwrap_or(
                     &WorkDay {
                         time_per_task: TimePerTask::new(),
-                        working_hours: self.work_week.working_hours_on_day(date)
+                        working_hours: self.working_hours_on(date)
                     }
                 )
                 .clone()
@@ -404,7 +455,7 @@ impl WorkWeek {
 
     /// Pure
     #[must_use] pub fn working_hours_on_day(&self, day: NaiveDate) -> WorkingHours {
-        self.days[&day.weekday()]
+        self.days[&day.weekday()].clone()
     }
 }
 
@@ -414,41 +465,115 @@ impl Default for WorkWeek {
 
         #[allow(deprecated)]
         let full_day = HourRange::new(NaiveTime::from_hms(8,0,0), NaiveTime::from_hms(17,0,0)).expect("This is const and will never fail");
+        let full_day = WorkingHours::new(vec![full_day]).expect("A single range never overlaps");
 
-        days.insert(Weekday::Mon, WorkingHours::new(Some(full_day)));
-        days.insert(Weekday::Tue, WorkingHours::new(Some(full_day)));
-        days.insert(Weekday::Wed, WorkingHours::new(Some(full_day)));
-        days.insert(Weekday::Thu, WorkingHours::new(Some(full_day)));
-        days.insert(Weekday::Fri, WorkingHours::new(Some(full_day)));
-        days.insert(Weekday::Sat, WorkingHours::new(None));
-        days.insert(Weekday::Sun, WorkingHours::new(None)); // There has got to be a better way of inserting all these? From an array?
+        days.insert(Weekday::Mon, full_day.clone());
+        days.insert(Weekday::Tue, full_day.clone());
+        days.insert(Weekday::Wed, full_day.clone());
+        days.insert(Weekday::Thu, full_day.clone());
+        days.insert(Weekday::Fri, full_day);
+        days.insert(Weekday::Sat, WorkingHours::default());
+        days.insert(Weekday::Sun, WorkingHours::default()); // There has got to be a better way of inserting all these? From an array?
 
         WorkWeek {days}
     }
 }
 
-#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+/// The hours of work on a day, e.g., 8-12 and 13-17 for a day with a lunch break
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "StoredWorkingHours")]
 pub struct WorkingHours {
-    hours_of_work: Option<HourRange>,
+    /// Sorted, and never overlapping
+    hours_of_work: Vec<HourRange>,
+}
+
+/// How `WorkingHours` are stored in the config. Older configs have a single range, or none.
+#[derive(Deserialize)]
+struct StoredWorkingHours {
+    hours_of_work: StoredHoursOfWork,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredHoursOfWork {
+    Ranges(Vec<HourRange>),
+    Range(Option<HourRange>),
+}
+
+impl TryFrom<StoredWorkingHours> for WorkingHours {
+    type Error = String;
+
+    fn try_from(stored: StoredWorkingHours) -> Result<Self, Self::Error> {
+        let hours = match stored.hours_of_work {
+            StoredHoursOfWork::Ranges(hours) => hours,
+            StoredHoursOfWork::Range(hours) => hours.into_iter().collect(),
+        };
+
+        // Ranges read from the config haven't been through `HourRange::new`
+        if let Some(hours) = hours.iter().find(|hours| hours.end <= hours.start) {
+            return Err(format!("working hours from {} to {} end before they start", hours.start, hours.end));
+        }
+
+        Self::new(hours)
+    }
 }
 
 impl WorkingHours {
-    #[must_use] pub fn new(hours: Option<HourRange>) -> Self {
-        Self {
-            hours_of_work: hours,
+    /// Pure
+    ///
+    /// # Errors
+    /// Returns an error if any of `hours` overlap
+    pub fn new(mut hours: Vec<HourRange>) -> Result<Self, String> {
+        hours.sort_by_key(|hours| hours.start);
+
+        if let Some(overlap) = hours.windows(2).find(|pair| pair[1].start < pair[0].end) {
+            return Err(format!(
+                "working hours from {} to {} overlap those from {} to {}",
+                overlap[0].start, overlap[0].end, overlap[1].start, overlap[1].end,
+            ));
         }
+
+        Ok(Self {
+            hours_of_work: hours,
+        })
+    }
+
+    /// Pure
+    ///
+    /// Returns the ranges of hours worked, in order
+    #[must_use] pub fn hours_of_work(&self) -> &[HourRange] {
+        &self.hours_of_work
     }
 
     /// Pure
+    ///
+    /// Returns the time worked, not counting the gaps between ranges
     #[must_use] pub fn working_time(&self) -> Duration {
-        match self.hours_of_work {
-            Some(hours) => hours.duration(),
-            None => Duration::zero(),
+        self.hours_of_work
+            .iter()
+            .map(HourRange::duration)
+            .sum()
+    }
+
+    /// Pure
+    ///
+    /// Returns these hours without the time in `off`, e.g., for an afternoon off. Time off in the
+    /// middle of a range splits it in two.
+    #[must_use] pub fn without(&self, off: HourRange) -> Self {
+        Self {
+            hours_of_work: self.hours_of_work
+                .iter()
+                .flat_map(|hours| [
+                    HourRange::new(hours.start, min(hours.end, off.start)),
+                    HourRange::new(max(hours.start, off.end), hours.end),
+                ])
+                .flatten()
+                .collect(),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HourRange {
     start: NaiveTime,
     end:   NaiveTime,
@@ -466,4 +591,119 @@ impl HourRange {
     #[must_use] pub fn duration(&self) -> Duration {
         self.end - self.start
     }
+
+    /// Pure
+    #[must_use] pub fn start(&self) -> NaiveTime {
+        self.start
+    }
+
+    /// Pure
+    #[must_use] pub fn end(&self) -> NaiveTime {
+        self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_horizon_reaches_the_latest_date_of_any_task() {
+        let today = NaiveDate::from_ymd_opt(2024, 12, 20).unwrap();
+        let task = |next_action_date: NaiveDate, due_date: DueDate| Task {
+            next_action_date,
+            due_date,
+            ..Task::default()
+        };
+
+        assert_eq!(Schedule::horizon_from(&[], today), 2024..=2024);
+        assert_eq!(Schedule::horizon_from(&[
+            task(today, DueDate::Date(NaiveDate::from_ymd_opt(2025, 1, 10).unwrap())),
+            task(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), DueDate::Asap),
+        ], today), 2024..=2025);
+        // A task that is never due is still worked on from its next action date
+        assert_eq!(Schedule::horizon_from(&[
+            task(NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(), DueDate::Never),
+        ], today), 2024..=2026);
+    }
+
+    fn time(h: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, 0, 0).unwrap()
+    }
+
+    fn range(start: u32, end: u32) -> HourRange {
+        HourRange::new(time(start), time(end)).unwrap()
+    }
+
+    #[test]
+    fn test_time_off_reduces_working_hours() {
+        let day = WorkingHours::new(vec![range(8, 17)]).unwrap();
+
+        // An afternoon or morning off leaves the rest of the day
+        assert_eq!(day.without(range(13, 18)).hours_of_work, vec![range(8, 13)]);
+        assert_eq!(day.without(range(7, 10)).hours_of_work, vec![range(10, 17)]);
+        // Time off in the middle splits the day
+        assert_eq!(day.without(range(11, 13)).hours_of_work, vec![range(8, 11), range(13, 17)]);
+        assert_eq!(day.without(range(6, 20)).hours_of_work, Vec::new());
+        assert_eq!(day.without(range(18, 20)).hours_of_work, vec![range(8, 17)]);
+
+        let split = WorkingHours::new(vec![range(8, 12), range(13, 17)]).unwrap();
+        assert_eq!(split.without(range(11, 14)).hours_of_work, vec![range(8, 11), range(14, 17)]);
+    }
+
+    #[test]
+    fn test_gaps_between_working_hours_are_not_worked() {
+        let day = WorkingHours::new(vec![range(13, 17), range(8, 12)]).unwrap();
+
+        assert_eq!(day.hours_of_work(), &[range(8, 12), range(13, 17)]);
+        assert_eq!(day.working_time(), Duration::hours(8));
+        assert_eq!(Schedule::time_remaining_of_hours(&day, time(7)), Some(Duration::hours(8)));
+        assert_eq!(Schedule::time_remaining_of_hours(&day, time(10)), Some(Duration::hours(6)));
+        // Lunch doesn't count
+        assert_eq!(Schedule::time_remaining_of_hours(&day, NaiveTime::from_hms_opt(12, 30, 0).unwrap()), Some(Duration::hours(4)));
+        assert_eq!(Schedule::time_remaining_of_hours(&day, time(18)), Some(Duration::zero()));
+        assert_eq!(Schedule::time_remaining_of_hours(&WorkingHours::default(), time(10)), None);
+    }
+
+    #[test]
+    fn test_overlapping_working_hours_are_rejected() {
+        assert!(WorkingHours::new(vec![range(8, 12), range(11, 17)]).is_err());
+        assert!(WorkingHours::new(vec![range(8, 17), range(12, 13)]).is_err());
+        assert!(WorkingHours::new(vec![range(8, 12), range(12, 17)]).is_ok());
+    }
+
+    #[test]
+    fn test_working_hours_config() {
+        let split = WorkingHours::new(vec![range(8, 12), range(13, 17)]).unwrap();
+        let json = serde_json::to_string(&split).unwrap();
+        assert_eq!(serde_json::from_str::<WorkingHours>(&json).unwrap(), split);
+
+        // Configs from before working hours could be split
+        let single: WorkingHours = serde_json::from_str(r#"{"hours_of_work":{"start":"08:00:00","end":"17:00:00"}}"#).unwrap();
+        assert_eq!(single.hours_of_work, vec![range(8, 17)]);
+        let none: WorkingHours = serde_json::from_str(r#"{"hours_of_work":null}"#).unwrap();
+        assert_eq!(none, WorkingHours::default());
+
+        assert!(serde_json::from_str::<WorkingHours>(
+            r#"{"hours_of_work":[{"start":"08:00:00","end":"12:00:00"},{"start":"11:00:00","end":"17:00:00"}]}"#
+        ).is_err());
+        assert!(serde_json::from_str::<WorkingHours>(r#"{"hours_of_work":[{"start":"12:00:00","end":"08:00:00"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_partial_days_off_are_still_work_days() {
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let afternoon = HourRange::new(NaiveTime::from_hms_opt(13, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+
+        let schedule = Schedule::new(vec![
+            DayOff::whole_day(monday, crate::days_off::Reason::Travel),
+            DayOff { hours: afternoon, ..DayOff::whole_day(tuesday, crate::days_off::Reason::Vacation) },
+        ], &Vec::new(), WorkWeek::default());
+
+        assert!(!schedule.is_work_day(monday));
+        assert!(schedule.is_work_day(tuesday));
+        assert_eq!(schedule.working_hours_on(tuesday).working_time(), Duration::hours(5));
+        assert_eq!(schedule.day_off(tuesday).map(|d| d.reason), Some(crate::days_off::Reason::Vacation));
+    }
 }
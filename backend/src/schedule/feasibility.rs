@@ -0,0 +1,62 @@
+//! Whether the work on tasks fits in the days before they are due
+
+use std::collections::BTreeMap;
+
+use chrono::{
+    NaiveDate,
+    Duration,
+};
+
+use crate::task::Id;
+
+use super::TimePerTask;
+
+/// The work a `Schedule` could not fit in the working hours of the days it was assigned to
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Feasibility {
+    /// The tasks that will miss their due date unless there is overtime, and how much overtime
+    pub at_risk: TimePerTask,
+    /// The days with more work assigned than working hours, and by how much
+    pub overbooked: BTreeMap<NaiveDate, Duration>,
+    /// The tasks with time remaining but no days to work on them, e.g., because their next
+    /// action is after their due date
+    pub unschedulable: Vec<Id>,
+}
+
+impl Feasibility {
+    /// Impure (modifies self)
+    ///
+    /// Records that `duration` of work on `task` was assigned to `day` beyond its working hours
+    pub(super) fn over_capacity(&mut self, day: NaiveDate, task: Id, duration: Duration) {
+        let over = self.at_risk.entry(task).or_insert_with(Duration::zero);
+        *over = *over + duration;
+        let overbooked = self.overbooked.entry(day).or_insert_with(Duration::zero);
+        *overbooked = *overbooked + duration;
+    }
+
+    /// Pure
+    ///
+    /// Returns whether all work fits in the working hours before it is due
+    #[must_use] pub fn is_feasible(&self) -> bool {
+        self.at_risk.is_empty() && self.unschedulable.is_empty()
+    }
+
+    /// Pure
+    ///
+    /// Returns how much overtime a task needs to be done by its due date, if any
+    #[must_use] pub fn time_over(&self, task: Id) -> Option<Duration> {
+        self.at_risk.get(&task).copied()
+    }
+
+    /// Pure
+    ///
+    /// Returns how much more work is assigned to a day than it has working hours, if any
+    #[must_use] pub fn time_overbooked(&self, day: NaiveDate) -> Option<Duration> {
+        self.overbooked.get(&day).copied()
+    }
+
+    /// Pure
+    #[must_use] pub fn is_unschedulable(&self, task: Id) -> bool {
+        self.unschedulable.contains(&task)
+    }
+}
@@ -0,0 +1,317 @@
+//! Ways of spreading the time remaining on tasks over the days they can be worked on
+
+use core::fmt::Display;
+use std::cmp::min;
+
+use chrono::{
+    NaiveDate,
+    Duration,
+};
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::Task;
+
+use super::Schedule;
+
+/// Decides how much of each task is worked on each day of a `Schedule`
+pub trait SchedulingStrategy {
+    /// Impure (modifies `schedule`)
+    ///
+    /// Assigns the time remaining on each of `tasks` to days in `schedule`
+    fn assign(&self, schedule: &mut Schedule, tasks: &[Task]);
+}
+
+/// Sorts tasks from first to last due, and schedules work on each as early as possible
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EarliestDueFirst;
+
+impl SchedulingStrategy for EarliestDueFirst {
+    fn assign(&self, schedule: &mut Schedule, tasks: &[Task]) {
+        let mut sorted_tasks: Vec<&Task> = tasks.iter().collect();
+        sorted_tasks.sort_by_key(|task| task.due_date);
+
+        for task in sorted_tasks {
+            if let Some(days) = schedule.work_days_for_task(task) {
+                fill(schedule, task, days);
+            }
+        }
+    }
+}
+
+/// Sorts tasks from last to first due, and schedules work on each as late as possible, i.e.,
+/// just in time for it to be due
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JustInTime;
+
+impl SchedulingStrategy for JustInTime {
+    fn assign(&self, schedule: &mut Schedule, tasks: &[Task]) {
+        let mut sorted_tasks: Vec<&Task> = tasks.iter().collect();
+        sorted_tasks.sort_by_key(|task| std::cmp::Reverse(task.due_date));
+
+        for task in sorted_tasks {
+            if let Some(mut days) = schedule.work_days_for_task(task) {
+                days.reverse();
+                fill(schedule, task, days);
+            }
+        }
+    }
+}
+
+/// Schedules the same amount of work on a task every day it can be worked on. Work that doesn't
+/// fit in a day is carried to the next, and whatever doesn't fit by the last day is over capacity.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EvenSpread;
+
+impl SchedulingStrategy for EvenSpread {
+    fn assign(&self, schedule: &mut Schedule, tasks: &[Task]) {
+        for task in tasks {
+            let Some(days) = schedule.work_days_for_task(task) else { continue };
+            let Ok(num_days) = i64::try_from(days.len()) else { continue };
+            if num_days == 0 {
+                continue;
+            }
+
+            // Spread the seconds that don't divide evenly one to a day, from the first day
+            let seconds = task.time_remaining().num_seconds();
+            let (per_day, extra) = (seconds / num_days, seconds % num_days);
+
+            // Track the time from earlier days that did not fit on them
+            let mut carried = Duration::zero();
+            let last_day = days.last().copied();
+
+            for (day, n) in days.into_iter().zip(0..) {
+                let time_to_assign = carried + Duration::seconds(per_day + i64::from(n < extra));
+                let workload_for_day = min(
+                    schedule.time_available_on_date(day).unwrap_or_else(Duration::zero),
+                    time_to_assign
+                );
+
+                if workload_for_day > Duration::zero() {
+                    schedule.assign(day, task, workload_for_day);
+                }
+                carried = time_to_assign - workload_for_day;
+            }
+
+            if let Some(day) = last_day.filter(|_| carried > Duration::zero()) {
+                schedule.assign_over_capacity(day, task, carried);
+            }
+        }
+    }
+}
+
+/// Impure (modifies `schedule`)
+///
+/// Assigns as much of `task` as fits on each of `days` in turn, and whatever doesn't fit to the
+/// last of them, over capacity
+fn fill(schedule: &mut Schedule, task: &Task, days: Vec<NaiveDate>) {
+    // Track the time that has not yet been assigned to a day
+    let mut time_to_assign = task.time_remaining();
+    let last_day = days.last().copied();
+
+    for day in days {
+        if time_to_assign <= Duration::zero() {
+            break; // Don't continue looping once all time is assigned
+        }
+
+        // Find how much time can be allocated to this day from this task
+        let workload_for_day = min(
+            schedule.time_available_on_date(day).unwrap_or_else(Duration::zero),
+            time_to_assign
+        );
+        if workload_for_day > Duration::zero() {
+            schedule.assign(day, task, workload_for_day);
+            time_to_assign = time_to_assign - workload_for_day;
+        }
+    }
+
+    // If time remains, assign to the final day
+    if let Some(day) = last_day.filter(|_| time_to_assign > Duration::zero()) {
+        schedule.assign_over_capacity(day, task, time_to_assign);
+    }
+}
+
+/// The strategies that can be picked in the config, e.g., to compare them in the calendar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// `EarliestDueFirst`
+    #[default]
+    Frontload,
+    /// `JustInTime`
+    Backload,
+    /// `EvenSpread`
+    Spread,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [Strategy::Frontload, Strategy::Backload, Strategy::Spread];
+}
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Strategy::Frontload => "Earliest due first",
+            Strategy::Backload  => "Just in time",
+            Strategy::Spread    => "Even spread",
+        })
+    }
+}
+
+impl SchedulingStrategy for Strategy {
+    fn assign(&self, schedule: &mut Schedule, tasks: &[Task]) {
+        match self {
+            Strategy::Frontload => EarliestDueFirst.assign(schedule, tasks),
+            Strategy::Backload  => JustInTime.assign(schedule, tasks),
+            Strategy::Spread    => EvenSpread.assign(schedule, tasks),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Weekday;
+
+    use super::*;
+    use crate::{
+        DueDate,
+        schedule::WorkWeek,
+        utils::today_date,
+    };
+
+    /// The Monday of the week after next, so no day in the tests is today or has passed
+    fn monday() -> NaiveDate {
+        let today = today_date();
+        today.week(Weekday::Mon).first_day() + Duration::weeks(2)
+    }
+
+    fn task(id: u32, hours: i64, due_date: NaiveDate) -> Task {
+        Task {
+            id: Some(id),
+            time_needed: Duration::hours(hours),
+            next_action_date: monday(),
+            due_date: DueDate::Date(due_date),
+            ..Task::default()
+        }
+    }
+
+    fn time_on(schedule: &Schedule, id: u32, date: NaiveDate) -> Duration {
+        schedule.get_time_per_task_on_day(date)
+            .and_then(|tasks| tasks.get(&Some(id)).copied())
+            .unwrap_or_else(Duration::zero)
+    }
+
+    fn schedule(tasks: &[Task], strategy: Strategy) -> Schedule {
+        Schedule::with_strategy(Vec::new(), tasks, WorkWeek::default(), &strategy)
+    }
+
+    #[test]
+    fn test_earliest_due_first_works_on_the_first_due_task_first() {
+        let (monday, wednesday) = (monday(), monday() + Duration::days(2));
+        // The later task is passed first, and would take Monday if the tasks weren't sorted
+        let tasks = [task(1, 9, wednesday), task(2, 9, monday)];
+
+        let schedule = schedule(&tasks, Strategy::Frontload);
+
+        assert_eq!(time_on(&schedule, 2, monday), Duration::hours(9));
+        assert_eq!(time_on(&schedule, 1, monday), Duration::zero());
+        assert_eq!(time_on(&schedule, 1, monday + Duration::days(1)), Duration::hours(9));
+    }
+
+    #[test]
+    fn test_just_in_time_works_on_tasks_as_late_as_possible() {
+        let (monday, wednesday) = (monday(), monday() + Duration::days(2));
+        let tasks = [task(1, 12, wednesday), task(2, 9, wednesday)];
+
+        let schedule = schedule(&tasks, Strategy::Backload);
+
+        assert_eq!(schedule.get_time_assigned_on_day(wednesday), Some(Duration::hours(9)));
+        assert_eq!(schedule.get_time_assigned_on_day(wednesday - Duration::days(1)), Some(Duration::hours(9)));
+        assert_eq!(schedule.get_time_assigned_on_day(monday), Some(Duration::hours(3)));
+        assert_eq!(time_on(&schedule, 1, monday) + time_on(&schedule, 2, monday), Duration::hours(3));
+    }
+
+    #[test]
+    fn test_even_spread_works_the_same_every_day() {
+        let friday = monday() + Duration::days(4);
+        let tasks = [task(1, 10, friday)];
+
+        let schedule = schedule(&tasks, Strategy::Spread);
+
+        for day in 0..5 {
+            assert_eq!(time_on(&schedule, 1, monday() + Duration::days(day)), Duration::hours(2));
+        }
+    }
+
+    #[test]
+    fn test_time_that_does_not_fit_goes_on_the_last_day() {
+        let tuesday = monday() + Duration::days(1);
+        let tasks = [task(1, 20, tuesday)];
+
+        let frontloaded = schedule(&tasks, Strategy::Frontload);
+        let backloaded = schedule(&tasks, Strategy::Backload);
+
+        assert_eq!(time_on(&frontloaded, 1, monday()), Duration::hours(9));
+        assert_eq!(time_on(&frontloaded, 1, tuesday), Duration::hours(11));
+        // Working backwards, the last day is the earliest
+        assert_eq!(time_on(&backloaded, 1, tuesday), Duration::hours(9));
+        assert_eq!(time_on(&backloaded, 1, monday()), Duration::hours(11));
+    }
+
+    #[test]
+    fn test_work_that_does_not_fit_is_reported() {
+        let tuesday = monday() + Duration::days(1);
+        let tasks = [task(1, 20, tuesday), task(2, 4, tuesday + Duration::days(1))];
+
+        for strategy in Strategy::ALL {
+            let feasibility = schedule(&tasks, strategy).feasibility().clone();
+
+            assert!(!feasibility.is_feasible(), "{strategy}");
+            assert_eq!(feasibility.time_over(Some(1)), Some(Duration::hours(2)), "{strategy}");
+            assert_eq!(feasibility.time_over(Some(2)), None, "{strategy}");
+            assert_eq!(feasibility.overbooked.values().sum::<Duration>(), Duration::hours(2), "{strategy}");
+        }
+
+        let frontloaded = schedule(&tasks, Strategy::Frontload);
+        assert_eq!(frontloaded.feasibility().time_overbooked(tuesday), Some(Duration::hours(2)));
+        // Spreading evenly carries the second task's share of the full days to the day it's due
+        let spread = schedule(&tasks, Strategy::Spread);
+        assert_eq!(spread.feasibility().time_overbooked(tuesday), Some(Duration::hours(2)));
+        assert_eq!(time_on(&spread, 2, tuesday + Duration::days(1)), Duration::hours(4));
+    }
+
+    #[test]
+    fn test_tasks_without_days_to_work_on_are_unschedulable() {
+        let tuesday = monday() + Duration::days(1);
+        let late = Task {
+            next_action_date: tuesday + Duration::days(1),
+            ..task(1, 2, tuesday)
+        };
+        let finished = Task {
+            time_used: Duration::hours(2),
+            ..late.clone()
+        };
+        let never_due = Task {
+            due_date: DueDate::Never,
+            ..task(2, 2, tuesday)
+        };
+
+        let feasibility = schedule(&[late, finished, never_due, task(3, 2, tuesday)], Strategy::Frontload).feasibility().clone();
+
+        assert_eq!(feasibility.unschedulable, vec![Some(1)]);
+        assert!(feasibility.at_risk.is_empty());
+        assert!(!feasibility.is_feasible());
+    }
+
+    #[test]
+    fn test_work_that_fits_is_feasible() {
+        let tasks = [task(1, 9, monday()), task(2, 4, monday() + Duration::days(1))];
+
+        for strategy in Strategy::ALL {
+            assert!(schedule(&tasks, strategy).feasibility().is_feasible(), "{strategy}");
+        }
+    }
+}
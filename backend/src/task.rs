@@ -25,8 +25,13 @@ pub struct Task {
     pub date_added:       NaiveDate,
     pub next_action_date: NaiveDate,
     pub due_date:         DueDate,
+    /// `None` for unfinished tasks, and for tasks finished before this was recorded
+    pub finished_date:    Option<NaiveDate>,
     pub id:               Id,
     pub links:            Vec<Hyperlink>,
+    /// The number of times the stored task has been updated when this copy was read.
+    /// Updating the task fails if it has been updated since.
+    pub revision:         u32,
 }
 
 impl Task {
@@ -45,6 +50,43 @@ impl Task {
     #[must_use] pub fn time_remaining(&self) -> Duration {
         max(self.time_needed - self.time_used, Duration::zero())
     }
+
+    /// Pure
+    ///
+    /// Returns the time between adding and finishing the task, if it has been finished
+    #[must_use] pub fn lead_time(&self) -> Option<Duration> {
+        Some(self.finished_date? - self.date_added)
+    }
+
+    /// Pure
+    ///
+    /// Combines two edits of `base`, for when `theirs` was saved while `mine` was being edited.
+    /// Each field changed in `mine` is kept, and every other field is taken from `theirs`, except
+    /// that time used is added up from both. The result has the revision of `theirs`, so it can be
+    /// saved over it.
+    #[allow(clippy::used_underscore_binding)]
+    #[must_use] pub fn merge(base: &Task, mine: &Task, theirs: &Task) -> Task {
+        fn pick<T: PartialEq + Clone>(base: &T, mine: &T, theirs: &T) -> T {
+            if mine == base {theirs.clone()} else {mine.clone()}
+        }
+
+        Task {
+            category:         pick(&base.category, &mine.category, &theirs.category),
+            finished:         pick(&base.finished, &mine.finished, &theirs.finished),
+            name:             pick(&base.name, &mine.name, &theirs.name),
+            _time_budgeted:   pick(&base._time_budgeted, &mine._time_budgeted, &theirs._time_budgeted),
+            time_needed:      pick(&base.time_needed, &mine.time_needed, &theirs.time_needed),
+            time_used:        theirs.time_used + (mine.time_used - base.time_used),
+            notes:            pick(&base.notes, &mine.notes, &theirs.notes),
+            date_added:       pick(&base.date_added, &mine.date_added, &theirs.date_added),
+            next_action_date: pick(&base.next_action_date, &mine.next_action_date, &theirs.next_action_date),
+            due_date:         pick(&base.due_date, &mine.due_date, &theirs.due_date),
+            finished_date:    pick(&base.finished_date, &mine.finished_date, &theirs.finished_date),
+            id:               theirs.id,
+            links:            pick(&base.links, &mine.links, &theirs.links),
+            revision:         theirs.revision,
+        }
+    }
 }
 
 impl std::default::Default for Task {
@@ -60,8 +102,10 @@ impl std::default::Default for Task {
             time_used:          Duration::zero(),
             notes:              String::new(),
             due_date:           DueDate::Asap,
+            finished_date:      None,
             id:                 None,
             links:              Vec::new(),
+            revision:           0,
         }
     }
 }
@@ -73,3 +117,39 @@ pub struct Hyperlink {
     pub display: String,
     pub id:      usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_both_sides_changes() {
+        let base = Task {
+            name: "Task".into(),
+            notes: "Notes".into(),
+            time_used: Duration::minutes(10),
+            id: Some(1),
+            ..Task::default()
+        };
+        let mine = Task {
+            name: "My name".into(),
+            time_used: Duration::minutes(15),
+            ..base.clone()
+        };
+        let theirs = Task {
+            notes: "Their notes".into(),
+            name: "Their name".into(),
+            time_used: Duration::minutes(30),
+            revision: 2,
+            ..base.clone()
+        };
+
+        assert_eq!(Task::merge(&base, &mine, &theirs), Task {
+            name: "My name".into(),
+            notes: "Their notes".into(),
+            time_used: Duration::minutes(35),
+            revision: 2,
+            ..base
+        });
+    }
+}
@@ -3,11 +3,11 @@ use std::path::Path;
 use std::io::{Error, ErrorKind,};
 
 use chrono::Duration;
-use anyhow::Result;
 
 use csv::Writer;
 
 use crate::Task;
+use crate::error::{BeavorError, Result};
 use crate::utils::{today_string, now_string};
 
 #[derive(Debug)]
@@ -27,9 +27,9 @@ impl TimeSheet {
             return Err(Error::new(ErrorKind::AlreadyExists, format!("Cannot create a logfile at: {path}\nFile exists!")).into());
         }
 
-        let mut logger = Self {writer:Writer::from_path(path)?};
+        let mut logger = Self {writer:Writer::from_path(path).map_err(Error::from)?};
 
-        logger.writer.write_record(["Task", "TaskID", "Time Worked", "Date", "Time"])?;
+        logger.writer.write_record(["Task", "TaskID", "Time Worked", "Date", "Time"]).map_err(Error::from)?;
 
         Ok(logger)
     }
@@ -49,18 +49,19 @@ impl TimeSheet {
     }
 
     /// Impure (writes to file)
-    /// # Panics
-    /// Panics if passed a task with a `None` `id`
     ///
     /// # Errors
-    /// Returns an error if the time cannot be written to file
+    /// Returns a `NotFound` error if passed a task with a `None` `id`, or an error if the time
+    /// cannot be written to file
     pub fn log_time (&mut self, time: Duration, task: &Task) -> Result<()> {
+        let id = task.id.ok_or_else(|| BeavorError::NotFound(format!("Task '{}' has no id, so its time cannot be logged", task.name)))?;
+
         Ok(self.writer.write_record([
             &task.name,
-            &task.id.expect("The task should have an id").to_string(),
+            &id.to_string(),
             &time.to_string(),
             &today_string(),
             &now_string(),
-        ])?)
+        ]).map_err(Error::from)?)
     }
 }
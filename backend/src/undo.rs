@@ -0,0 +1,263 @@
+use core::fmt::Display;
+
+use chrono::{
+    NaiveDate,
+    Duration,
+};
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::{
+    Task,
+    Hyperlink,
+    DueDate,
+    DayOff,
+    error::{
+        BeavorError,
+        Result,
+    },
+};
+
+/// The most edits kept in the undo log. Older edits are forgotten.
+pub const UNDO_LIMIT: i64 = 100;
+
+/// A change to the database that can be undone, holding the state on either side of it
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// `None` means the task did not exist or was in the trash
+    Task {
+        before: Option<Task>,
+        after:  Option<Task>,
+    },
+    VacationDay {
+        date:  NaiveDate,
+        added: bool,
+    },
+    /// Days off added or deleted together, e.g., a whole trip
+    DaysOff {
+        days:  Vec<DayOff>,
+        added: bool,
+    },
+}
+
+impl Edit {
+    /// Pure
+    ///
+    /// Returns the edit that reverses this one
+    #[must_use] pub fn inverse(self) -> Self {
+        match self {
+            Edit::Task { before, after } => Edit::Task { before: after, after: before },
+            Edit::VacationDay { date, added } => Edit::VacationDay { date, added: !added },
+            Edit::DaysOff { days, added } => Edit::DaysOff { days, added: !added },
+        }
+    }
+
+    /// Pure
+    ///
+    /// Returns the id of the task this edit changes, if any
+    #[must_use] pub fn task_id(&self) -> Option<u32> {
+        match self {
+            Edit::Task { before, after } => after.as_ref().or(before.as_ref()).and_then(|t| t.id),
+            Edit::VacationDay { .. } | Edit::DaysOff { .. } => None,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(&StoredEdit::from(self))
+            .expect("StoredEdit contains only strings, numbers and dates, so always serializes")
+    }
+
+    pub(crate) fn from_json(json: &str) -> Result<Self> {
+        let invalid = |reason: String| BeavorError::InvalidRow {
+            table:  "undo_log",
+            id:     None,
+            column: "Edit",
+            reason,
+        };
+
+        serde_json::from_str::<StoredEdit>(json)
+            .map_err(|e| invalid(e.to_string()))?
+            .try_into()
+            .map_err(invalid)
+    }
+}
+
+/// Which end of the log an edit is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stack {
+    Undo,
+    Redo,
+}
+
+impl Display for Stack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Stack::Undo => "undo",
+            Stack::Redo => "redo",
+        })
+    }
+}
+
+/// `Edit` as it is written to `undo_log`
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize)]
+enum StoredEdit {
+    Task {
+        before: Option<StoredTask>,
+        after:  Option<StoredTask>,
+    },
+    VacationDay {
+        date:  NaiveDate,
+        added: bool,
+    },
+    DaysOff {
+        days:  Vec<DayOff>,
+        added: bool,
+    },
+}
+
+/// `Task` with its durations in seconds and its due date as text, matching the tasks table
+#[derive(Serialize, Deserialize)]
+struct StoredTask {
+    category:         String,
+    finished:         bool,
+    name:             String,
+    time_budgeted:    i64,
+    time_needed:      i64,
+    time_used:        i64,
+    notes:            String,
+    date_added:       NaiveDate,
+    next_action_date: NaiveDate,
+    due_date:         String,
+    finished_date:    Option<NaiveDate>,
+    id:               Option<u32>,
+    links:            Vec<Hyperlink>,
+    /// Missing from edits logged before tasks had revisions
+    #[serde(default)]
+    revision:         u32,
+}
+
+impl From<&Edit> for StoredEdit {
+    fn from(value: &Edit) -> Self {
+        match value {
+            Edit::Task { before, after } => StoredEdit::Task {
+                before: before.as_ref().map(StoredTask::from),
+                after:  after.as_ref().map(StoredTask::from),
+            },
+            Edit::VacationDay { date, added } => StoredEdit::VacationDay { date: *date, added: *added },
+            Edit::DaysOff { days, added } => StoredEdit::DaysOff { days: days.clone(), added: *added },
+        }
+    }
+}
+
+impl TryFrom<StoredEdit> for Edit {
+    type Error = String;
+
+    fn try_from(value: StoredEdit) -> Result<Self, Self::Error> {
+        Ok(match value {
+            StoredEdit::Task { before, after } => Edit::Task {
+                before: before.map(Task::try_from).transpose()?,
+                after:  after.map(Task::try_from).transpose()?,
+            },
+            StoredEdit::VacationDay { date, added } => Edit::VacationDay { date, added },
+            StoredEdit::DaysOff { days, added } => Edit::DaysOff { days, added },
+        })
+    }
+}
+
+impl From<&Task> for StoredTask {
+    #[allow(clippy::used_underscore_binding)]
+    fn from(task: &Task) -> Self {
+        Self {
+            category:         task.category.clone(),
+            finished:         task.finished,
+            name:             task.name.clone(),
+            time_budgeted:    task._time_budgeted.num_seconds(),
+            time_needed:      task.time_needed.num_seconds(),
+            time_used:        task.time_used.num_seconds(),
+            notes:            task.notes.clone(),
+            date_added:       task.date_added,
+            next_action_date: task.next_action_date,
+            due_date:         task.due_date.to_string(),
+            finished_date:    task.finished_date,
+            id:               task.id,
+            links:            task.links.clone(),
+            revision:         task.revision,
+        }
+    }
+}
+
+impl TryFrom<StoredTask> for Task {
+    type Error = String;
+
+    fn try_from(stored: StoredTask) -> Result<Self, Self::Error> {
+        Ok(Self {
+            category:         stored.category,
+            finished:         stored.finished,
+            name:             stored.name,
+            _time_budgeted:   Duration::seconds(stored.time_budgeted),
+            time_needed:      Duration::seconds(stored.time_needed),
+            time_used:        Duration::seconds(stored.time_used),
+            notes:            stored.notes,
+            date_added:       stored.date_added,
+            next_action_date: stored.next_action_date,
+            due_date:         DueDate::try_from(stored.due_date).map_err(|e| e.to_string())?,
+            finished_date:    stored.finished_date,
+            id:               stored.id,
+            links:            stored.links,
+            revision:         stored.revision,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_json_round_trip() {
+        let task = Task {
+            name: "Task".into(),
+            time_needed: Duration::minutes(90),
+            time_used: Duration::seconds(61),
+            due_date: DueDate::Never,
+            id: Some(3),
+            links: vec![Hyperlink { url: "a".into(), display: "A".into(), id: 0 }],
+            ..Task::default()
+        };
+
+        let edits = [
+            Edit::Task { before: None, after: Some(task.clone()) },
+            Edit::Task { before: Some(task.clone()), after: Some(Task { finished: true, ..task }) },
+            Edit::VacationDay { date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(), added: true },
+            Edit::DaysOff {
+                days:  DayOff::range(
+                    NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+                    NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+                    crate::days_off::Reason::Travel,
+                    "Conference",
+                    None,
+                ),
+                added: false,
+            },
+        ];
+
+        for edit in edits {
+            assert_eq!(Edit::from_json(&edit.to_json()).unwrap(), edit);
+        }
+    }
+
+    #[test]
+    fn test_inverse_is_its_own_inverse() {
+        let edit = Edit::Task { before: None, after: Some(Task::default()) };
+        assert_eq!(edit.clone().inverse(), Edit::Task { before: Some(Task::default()), after: None });
+        assert_eq!(edit.clone().inverse().inverse(), edit);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        assert_eq!(Edit::VacationDay { date, added: true }.inverse(), Edit::VacationDay { date, added: false });
+    }
+}
@@ -2,6 +2,7 @@ use chrono::{
     Local,
     NaiveDate,
     NaiveTime,
+    NaiveDateTime,
 };
 
 use anyhow::Result;
@@ -56,6 +57,32 @@ pub fn parse_time(time_string: &str) -> Result<NaiveTime> {
     format_time(now_time())
 }
 
+/// Pure
+#[must_use] pub fn format_datetime(datetime: NaiveDateTime) -> String {
+    datetime.format("%F %H:%M:%S").to_string()
+}
+
+/// Pure
+/// # Errors
+/// Returns an error if the string cannot be parsed as an `%F %H:%M:%S` datetime
+pub fn parse_datetime(datetime_string: &str) -> Result<NaiveDateTime> {
+    Ok(NaiveDateTime::parse_from_str(datetime_string, "%F %H:%M:%S")?)
+}
+
+/// Impure (reads system clock)
+#[must_use] pub fn now_datetime() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// Impure (reads environment)
+///
+/// Returns the name of the user running beavor, for recording who made a change
+#[must_use] pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".into())
+}
+
 #[allow(clippy::zero_prefixed_literal)]
 #[cfg(test)]
 mod tests {
@@ -79,4 +106,10 @@ mod tests {
         let t = NaiveTime::from_hms_opt(7,01,10).unwrap();
         assert_eq!(parse_time(&format_time(t)).unwrap(), t);
     }
+
+    #[test]
+    fn test_parse_format_datetime() {
+        let dt = NaiveDate::from_ymd_opt(1971,01,10).unwrap().and_hms_opt(7,01,10).unwrap();
+        assert_eq!(parse_datetime(&format_datetime(dt)).unwrap(), dt);
+    }
 }
@@ -1,18 +1,28 @@
-use std::fs;
-use std::collections::HashSet;
+use std::path::Path;
+use std::ops::RangeInclusive;
+use std::collections::{
+    HashSet,
+    HashMap,
+};
 use std::sync::{Arc, Mutex};
 
 use serde::{Deserialize, Serialize};
 
 use tokio::sync::oneshot;
 
-use chrono::NaiveDate;
+use chrono::{
+    NaiveDate,
+    Duration,
+};
 
 use iced::widget::{
     container,
     row,
     column,
     text,
+    button,
+    pick_list,
+    Row,
     rule::Rule,
     combo_box::State as ComboBoxState,
 };
@@ -30,14 +40,37 @@ use iced::{
     Alignment,
     font,
     window,
+    keyboard,
+    event,
+    Event,
 };
 
 use backend::{
     DatabaseManager,
+    BeavorError,
+    ArchiveFilter,
     Task,
+    TrashedTask,
+    TaskChange,
+    Edit,
     Schedule,
-    schedule::WorkWeek,
+    schedule::{
+        WorkWeek,
+        Strategy,
+    },
     TimeSheet,
+    BackupPolicy,
+    ChangeDetector,
+    HolidaySource,
+    FetchedHolidays,
+    holidays::Backoff,
+    DayOff,
+};
+
+mod profiles;
+use profiles::{
+    Profile,
+    Profiles,
 };
 
 mod widgets;
@@ -47,41 +80,45 @@ use widgets::{
         Message as CalendarMessage,
         State as CalendarState,
     },
-    task_scroller,
+    task_scroller::{
+        task_scroller,
+        SearchMessage,
+        SearchState,
+    },
     task_editor::{
         task_editor,
         DisplayedTask,
         TimerMessage,
+        EditorTab,
+        ConflictResolution,
     },
     confirm_modal,
     error_bar,
     error_bar::State as ErrorBarState,
+    archive::{
+        archive,
+        Message as ArchiveMessage,
+        State as ArchiveState,
+    },
+    trash,
+    profile_picker::{
+        profile_picker,
+        ProfileMessage,
+    },
+    restore_dialog::{
+        restore_dialog,
+        OpenFailure,
+        RestoreMessage,
+    },
 };
 
 use widgets::task_editor::UpdateDraftTask;
 
-const CONFIG_FILE_PATH: &str = "./resources/config.json";
-const WORKLIST_PATH:    &str = "worklist.db";
-const TIMESHEET_PATH:   &str = "timesheet.csv";
-
 fn main() {
-    let default = Settings::<Flags>::default();
-
-    let flags: Flags = serde_json::from_str(
-        &fs::read_to_string(CONFIG_FILE_PATH)
-            .unwrap_or_else(|_| {
-                let default = serde_json::to_string_pretty(&Flags::default())
-                    .expect("Flags::default() serializes correctly by definition");
-
-                fs::write(CONFIG_FILE_PATH, &default)
-                    .expect("Panics if cannot write to filesystem");
+    let default = Settings::<Profiles>::default();
 
-                default
-            })
-    ).expect("Panics if config file incorrectly formatted");
-
-    let settings: Settings<Flags> = Settings {
-        flags,
+    let settings: Settings<Profiles> = Settings {
+        flags: Profiles::load(),
         id: default.id,
         window: default.window,
         default_font: default.default_font,
@@ -94,9 +131,42 @@ fn main() {
         .expect("Application failed");
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Flags {
     work_week: WorkWeek,
+    /// Deleted tasks are removed for good once they have been in the trash this long
+    trash_retention_days: u32,
+    /// How often the database is backed up
+    backup_interval_hours: u32,
+    /// The most backups kept. Snapshots taken before upgrading the database are kept regardless.
+    backups_kept: usize,
+    /// Where statutory holidays come from
+    holidays: HolidaySource,
+    /// How work is spread over the days before tasks are due
+    strategy: Strategy,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            work_week: WorkWeek::default(),
+            trash_retention_days: 30,
+            backup_interval_hours: 24,
+            backups_kept: 7,
+            holidays: HolidaySource::default(),
+            strategy: Strategy::default(),
+        }
+    }
+}
+
+/// What is shown in the list on the left
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pane {
+    #[default]
+    Open,
+    Archive,
+    Trash,
 }
 
 #[derive(Debug, Clone)]
@@ -117,6 +187,8 @@ pub enum ModalType {
     NextAction,
     DueDate,
     Confirm(ConfirmationRequest),
+    /// Saving failed because the task was saved elsewhere. Holds the task as it is saved now
+    Conflict(Box<Task>),
 }
 
 #[derive(Debug, Clone)]
@@ -124,6 +196,16 @@ pub enum MutateMessage {
     SaveDraftTask,
     ForceDeleteTask,
     VacationStatus(NaiveDate, bool),
+    /// Book days off, e.g., a whole trip at once
+    AddDaysOff(Vec<DayOff>),
+    /// Clear the vacation and travel days on these dates
+    DeleteDaysOff(Vec<NaiveDate>),
+    ReopenTask(Task),
+    RestoreTask(u32),
+    /// Move the displayed task to the named profile
+    MoveTask(String),
+    Undo,
+    Redo,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -136,9 +218,17 @@ pub enum Message {
     TryDeleteTask,
     UpdateDraftTask(UpdateDraftTask),
     Modal(ModalMessage),
+    ResolveConflict(ConflictResolution),
     TryNewTask,
     Mutate(MutateMessage),
     Loaded(State),
+    OpenFailed(Box<OpenFailure>),
+    Restore(RestoreMessage),
+    BackupIfDue,
+    /// Something other than this window wrote to the database
+    DatabaseChanged,
+    /// Whether new holidays were stored, or why they could not be fetched
+    HolidaysRefreshed(Result<bool, String>),
     SetEditingLinkID(Option<usize>),
     Open(String),
     None,
@@ -146,6 +236,16 @@ pub enum Message {
     Timer(TimerMessage),
     UpdateFlags(Flags),
     Error(Option<String>),
+    Archive(ArchiveMessage),
+    ShowEditorTab(EditorTab),
+    HistoryLoaded(Vec<TaskChange>),
+    ShowPane(Pane),
+    TrashLoaded(Vec<TrashedTask>),
+    Search(SearchMessage),
+    Profile(ProfileMessage),
+    /// The result of work started for the profile with this database. It is dropped if another
+    /// profile has been opened since, so that it can't end up in the wrong database.
+    ForProfile(String, Box<Message>),
 }
 
 #[derive(Debug, Clone)]
@@ -157,17 +257,81 @@ pub struct State {
     modal_state:    ModalType,
     error_bar:      ErrorBarState,
     calendar_state: CalendarState,
-    flags:          Flags,
+    archive_state:  ArchiveState,
+    pane:           Pane,
+    trash:          Vec<TrashedTask>,
+    search:         SearchState,
+    profiles:       Profiles,
+    profile:        Profile,
 }
 
 #[derive(Debug, Clone)]
 pub struct Cache {
     loaded_tasks: Vec<Task>,
     loaded_schedule: Schedule,
+    /// The schedule with every strategy, to compare them in the calendar
+    compared_schedules: HashMap<Strategy, Schedule>,
     categories: ComboBoxState<String>,
 }
 
+impl Cache {
+    async fn load(db: DatabaseManager, flags: Flags) -> Result<Self, BeavorError> {
+        let tasks = db.open_tasks().await?;
+
+        let mut compared_schedules = HashMap::new();
+        for strategy in Strategy::ALL {
+            compared_schedules.insert(strategy, db.schedule(flags.work_week.clone(), &tasks, &strategy).await?);
+        }
+
+        Ok(Self {
+            loaded_schedule: compared_schedules[&flags.strategy].clone(),
+            compared_schedules,
+            categories: ComboBoxState::new(Beavor::unique_categories(&tasks)),
+            loaded_tasks: tasks,
+        })
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            loaded_tasks: Vec::new(),
+            loaded_schedule: Schedule::default(),
+            compared_schedules: HashMap::new(),
+            categories: ComboBoxState::new(Vec::new()),
+        }
+    }
+}
+
+/// How far along watching the database for changes made by other programs is
+enum Watcher {
+    Starting(DatabaseManager),
+    Watching(ChangeDetector),
+    Failed,
+}
+
+/// How often stored holidays are checked once they are up to date, so that a new year's are
+/// fetched soon after it starts
+const HOLIDAY_CHECK_INTERVAL: iced::time::Duration = iced::time::Duration::from_secs(60 * 60);
+
+/// Fetching holidays in the background, waiting longer after each failure
+struct HolidayRefresh {
+    db:      DatabaseManager,
+    source:  HolidaySource,
+    /// The years the schedule reaches, from `Schedule::horizon`
+    years:   RangeInclusive<i32>,
+    backoff: Backoff,
+    /// How long to wait before the next attempt. `None` before the first
+    wait:    Option<iced::time::Duration>,
+}
+
 enum Beavor {
+    ChoosingProfile {
+        profiles: Profiles,
+        new_name: String,
+    },
+    /// The database of a profile couldn't be opened
+    Restoring(Box<OpenFailure>),
     Loading,
     Loaded(State),
 }
@@ -176,40 +340,21 @@ impl Application for Beavor {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = Flags;
+    type Flags = Profiles;
+
+    fn new(profiles: Self::Flags) -> (Beavor, iced::Command<Message>) {
+        // Only ask which profile to use if there is a choice to make
+        let (beavor, load) = if profiles.len() > 1 {
+            (Self::ChoosingProfile { profiles, new_name: String::new() }, Command::none())
+        } else {
+            let name = profiles.most_recent().name.clone();
+            (Self::Loading, Self::load_profile(profiles, &name))
+        };
 
-    fn new(flags: Self::Flags) -> (Beavor, iced::Command<Message>) {
         (
-            Self::Loading,
+            beavor,
             Command::batch(vec![
-                Command::perform(async {
-                    let db = match DatabaseManager::new(WORKLIST_PATH).await {
-                        Ok(db) => db,
-                        Err(_) => DatabaseManager::with_new_database(WORKLIST_PATH).await.expect("Should be able to create database"),
-                    };
-
-                    let tasks = db.open_tasks().await;
-
-                    let timesheet: Arc<Mutex<TimeSheet>> = Arc::new(Mutex::new(match TimeSheet::new_timesheet(TIMESHEET_PATH) {
-                        Ok(logger) => logger,
-                        Err(_) => TimeSheet::open(TIMESHEET_PATH).expect("Should be able to open timesheet"),
-                    }));
-
-                    State {
-                        cache: Cache {
-                            loaded_schedule: db.schedule(flags.work_week.clone(), &tasks).await,
-                            categories: ComboBoxState::new(Self::unique_categories(&tasks)),
-                            loaded_tasks: tasks,
-                        }, // TODO this should call the same code that refreshes the cache
-                        db,
-                        timesheet,
-                        displayed_task: DisplayedTask::default(),
-                        modal_state:    ModalType::None,
-                        error_bar:      ErrorBarState::default(),
-                        calendar_state: CalendarState::default(),
-                        flags,
-                    }
-                }, Message::Loaded),
+                load,
                 font::load(iced_aw::graphics::icons::ICON_FONT_BYTES).map(|_| Message::None),
                 window::change_icon(window::icon::from_file_data(
                     include_bytes!("../../resources/logo.png"),
@@ -220,27 +365,68 @@ impl Application for Beavor {
     }
 
     fn title(&self) -> String {
-        String::from("Beavor")
+        match self {
+            Beavor::Loaded(state) => format!("Beavor - {}", state.profile.name),
+            _ => String::from("Beavor"),
+        }
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        if let Message::ForProfile(database, message) = message {
+            let still_open = matches!(self, Beavor::Loaded(state) if state.profile.database == database);
+            // Otherwise the profile was switched while this was running
+            return if still_open { self.update(*message) } else { Command::none() };
+        }
+
         match self {
+            Beavor::ChoosingProfile { .. } => self.update_choosing_profile(message),
+            Beavor::Restoring(_) => self.update_restoring(message),
             Beavor::Loading => self.update_loading(message),
-            Beavor::Loaded(_) => self.update_loaded(message),
+            Beavor::Loaded(state) => {
+                // Everything started while a profile is open is for that profile
+                let database = state.profile.database.clone();
+                self.update_loaded(message)
+                    .map(move |m| Message::ForProfile(database.clone(), Box::new(m)))
+            },
         }
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
         let content: Element<Message> = match self {
+            Beavor::ChoosingProfile { profiles, new_name } => profile_picker(profiles, new_name).into(),
+            Beavor::Restoring(failure) => restore_dialog(failure).into(),
             Beavor::Loading => text("Loading...").into(),
             Beavor::Loaded(state) =>
                 column![
+                    Self::profile_bar(state),
+                    Rule::horizontal(4),
                     row![
-                        task_scroller(
-                            &state.cache.loaded_tasks,
-                            state.calendar_state.filter_date.as_ref(),
-                            &state.cache.loaded_schedule
-                        )
+                        column![
+                            row![
+                                button("Open").on_press_maybe(
+                                    (state.pane != Pane::Open).then_some(Message::ShowPane(Pane::Open))
+                                ),
+                                button("Archive").on_press_maybe(
+                                    (state.pane != Pane::Archive).then_some(Message::ShowPane(Pane::Archive))
+                                ),
+                                button("Trash").on_press_maybe(
+                                    (state.pane != Pane::Trash).then_some(Message::ShowPane(Pane::Trash))
+                                ),
+                            ]
+                                .spacing(4)
+                                .padding(4),
+                            match state.pane {
+                                Pane::Open => task_scroller(
+                                    &state.cache.loaded_tasks,
+                                    state.calendar_state.filter_date.as_ref(),
+                                    &state.cache.loaded_schedule,
+                                    &state.search
+                                ),
+                                Pane::Archive => archive(&state.archive_state),
+                                Pane::Trash => trash(&state.trash),
+                            }
+                                .height(Length::Fill),
+                        ]
                             .width(Length::FillPortion(2))
                             .height(Length::FillPortion(1)),
                         Rule::vertical(4),
@@ -253,7 +439,7 @@ impl Application for Beavor {
                             .width(Length::FillPortion(3))
                             .height(Length::FillPortion(1)),
                         Rule::vertical(4),
-                        calendar(&state.cache.loaded_schedule, &state.calendar_state),
+                        calendar(&state.cache.loaded_schedule, &state.cache.compared_schedules, &state.calendar_state),
                         confirm_modal(&state.modal_state),
                     ]
                         .align_items(Alignment::End)
@@ -274,30 +460,337 @@ impl Application for Beavor {
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        iced::time::every(iced::time::Duration::from_secs(1)).map(Message::Tick)
+        Subscription::batch([
+            iced::time::every(iced::time::Duration::from_secs(1)).map(Message::Tick),
+            iced::subscription::events_with(Self::undo_shortcut),
+            iced::time::every(iced::time::Duration::from_secs(60 * 60)).map(|_| Message::BackupIfDue),
+            match self {
+                Beavor::Loaded(state) => Self::watch_database(state.db.clone(), state.profile.database.clone()),
+                _ => Subscription::none(),
+            },
+            match self {
+                Beavor::Loaded(state) => Self::refresh_holidays(
+                    state.db.clone(),
+                    &state.profile.database,
+                    state.profile.flags.holidays.clone(),
+                    Schedule::horizon(&state.cache.loaded_tasks),
+                ),
+                _ => Subscription::none(),
+            },
+        ])
     }
 }
 
 impl Beavor {
+    /// Ctrl+Z undoes and Ctrl+Shift+Z redoes, unless a widget has already handled the key press
+    fn undo_shortcut(event: Event, status: event::Status) -> Option<Message> {
+        match (event, status) {
+            (Event::Keyboard(keyboard::Event::KeyPressed { key_code: keyboard::KeyCode::Z, modifiers }), event::Status::Ignored)
+                if modifiers.command() => Some(Message::Mutate(
+                    if modifiers.shift() {MutateMessage::Redo} else {MutateMessage::Undo}
+                )),
+            _ => None,
+        }
+    }
+
+    /// Shows the current profile, and lets the displayed task be moved to another one
+    fn profile_bar(state: &State) -> Row<'_, Message> {
+        let bar = row![
+            text(format!("Profile: {}", state.profile.name)),
+            button("Switch").on_press(Message::Profile(ProfileMessage::TrySwitch)),
+        ]
+            .align_items(Alignment::Center)
+            .spacing(8);
+
+        // Only saved tasks can be moved, and moving discards unsaved changes
+        if state.displayed_task.draft.id.is_some() && state.displayed_task.is_unmodified() && state.profiles.len() > 1 {
+            bar.push(pick_list(
+                state.profiles.all()
+                    .into_iter()
+                    .filter(|p| p.name != state.profile.name)
+                    .map(|p| p.name.clone())
+                    .collect::<Vec<String>>(),
+                None,
+                |name| Message::Mutate(MutateMessage::MoveTask(name))
+            )
+                .placeholder("Move task to...")
+            )
+        } else {
+            bar
+        }
+    }
+
+    fn update_choosing_profile(&mut self, message: Message) -> Command<Message> {
+        let Beavor::ChoosingProfile { profiles, new_name } = self else {
+            panic!("Should never happen")
+        };
+
+        let chosen = match message {
+            Message::Profile(ProfileMessage::NewName(name)) => {
+                *new_name = name;
+                None
+            },
+            Message::Profile(ProfileMessage::Create) => profiles.create(new_name).map(|p| p.name.clone()),
+            Message::Profile(ProfileMessage::Choose(name)) => Some(name),
+            // Nothing else means anything until a profile is loaded
+            _ => None,
+        };
+
+        match chosen {
+            Some(name) => match std::mem::replace(self, Beavor::Loading) {
+                Beavor::ChoosingProfile { profiles, .. } => Self::load_profile(profiles, &name),
+                _ => panic!("Should never happen"),
+            },
+            None => Command::none(),
+        }
+    }
+
+    /// Opens the database and timesheet of a profile, creating them if they don't exist yet.
+    /// A missing database is only created if there are no backups of it to restore instead.
+    fn load_profile(mut profiles: Profiles, name: &str) -> Command<Message> {
+        profiles.mark_used(name);
+        // Remembering which profile was used last is a convenience, so failing to is not fatal
+        let saved = profiles.save();
+
+        let profile = profiles.get(name).expect("Profiles are only loaded by name from the list").clone();
+
+        Command::perform(async move {
+            let opened = if Path::new(&profile.database).exists() {
+                DatabaseManager::new(&profile.database).await
+            } else {
+                match profile.backup_policy().backups() {
+                    Ok(backups) if backups.is_empty() => DatabaseManager::with_new_database(&profile.database).await,
+                    Ok(_) => Err(BeavorError::NotFound(format!("{} is missing", profile.database))),
+                    Err(e) => Err(e),
+                }
+            };
+
+            let warning = saved.err().map(|e| format!("Could not save profiles: {e}"));
+            Self::finish_loading(opened, profiles, profile, warning).await
+        }, std::convert::identity)
+    }
+
+    /// Loads the rest of a profile once its database is open, or offers to restore the database if
+    /// it couldn't be opened
+    async fn finish_loading(opened: Result<DatabaseManager, BeavorError>, profiles: Profiles, profile: Profile, warning: Option<String>) -> Message {
+        let db = match opened {
+            Ok(db) => db,
+            Err(e) => return Message::OpenFailed(Box::new(OpenFailure {
+                error:         e.to_string(),
+                latest_backup: DatabaseManager::latest_good_backup(&profile.backup_policy()).await.ok().flatten(),
+                missing:       !Path::new(&profile.database).exists(),
+                profiles,
+                profile,
+            })),
+        };
+
+        // Empty the trash of anything past its retention period. Failing to do so is not fatal
+        let purged = db.purge_trash(Duration::days(profile.flags.trash_retention_days.into())).await;
+
+        let timesheet: Arc<Mutex<TimeSheet>> = Arc::new(Mutex::new(match TimeSheet::new_timesheet(&profile.timesheet) {
+            Ok(logger) => logger,
+            Err(_) => TimeSheet::open(&profile.timesheet).expect("Should be able to open timesheet"),
+        }));
+
+        Message::Loaded(State {
+            cache: Cache::default(), // Filled in by a refresh as soon as this is loaded
+            db,
+            timesheet,
+            displayed_task: DisplayedTask::default(),
+            modal_state:    ModalType::None,
+            error_bar:      ErrorBarState {
+                error:    purged.err().map(|e| format!("Could not empty the trash: {e}")).or(warning),
+                holidays: None,
+            },
+            calendar_state: CalendarState::default(),
+            archive_state:  ArchiveState::default(),
+            pane:           Pane::default(),
+            trash:          Vec::new(),
+            search:         SearchState::default(),
+            profiles,
+            profile,
+        })
+    }
+
+    fn update_restoring(&mut self, message: Message) -> Command<Message> {
+        let Message::Restore(restore_message) = message else {
+            // Nothing else means anything until a database is open
+            return Command::none();
+        };
+
+        let Beavor::Restoring(failure) = std::mem::replace(self, Beavor::Loading) else {
+            panic!("Should never happen")
+        };
+        let OpenFailure { profiles, profile, latest_backup, .. } = *failure;
+
+        match restore_message {
+            RestoreMessage::Restore => {
+                let backup = latest_backup.expect("Restoring is only offered when there is a backup");
+                Command::perform(async move {
+                    let opened = DatabaseManager::restore_from(&backup.path, &profile.database).await;
+                    Self::finish_loading(opened, profiles, profile, None).await
+                }, std::convert::identity)
+            },
+            RestoreMessage::StartEmpty => Command::perform(async move {
+                let opened = DatabaseManager::with_new_database(&profile.database).await;
+                Self::finish_loading(opened, profiles, profile, None).await
+            }, std::convert::identity),
+            RestoreMessage::ChooseProfile => {
+                *self = Beavor::ChoosingProfile { profiles, new_name: String::new() };
+                Command::none()
+            },
+        }
+    }
+
+    /// Backs up the database, if the last backup is old enough
+    fn backup_if_due(db: DatabaseManager, policy: BackupPolicy) -> Command<Message> {
+        Command::perform(async move {
+            db.backup_if_due(&policy).await
+        }, |r| Self::or_error(r.map(|_| Message::None)))
+    }
+
+    /// Sends `DatabaseChanged` whenever something else writes to the database.
+    /// `path` identifies the database, so that switching profiles watches the new one instead.
+    fn watch_database(db: DatabaseManager, path: String) -> Subscription<Message> {
+        iced::subscription::unfold(path, Watcher::Starting(db), |watcher| async move {
+            match watcher {
+                Watcher::Starting(db) => match db.watch(iced::time::Duration::from_secs(1)).await {
+                    Ok(detector) => (Message::None, Watcher::Watching(detector)),
+                    Err(e) => (Message::Error(Some(format!("Could not watch the database for changes: {e}"))), Watcher::Failed),
+                },
+                Watcher::Watching(mut detector) => match detector.changed().await {
+                    Ok(()) => (Message::DatabaseChanged, Watcher::Watching(detector)),
+                    Err(e) => (Message::Error(Some(format!("Stopped watching the database for changes: {e}"))), Watcher::Failed),
+                },
+                Watcher::Failed => iced::futures::future::pending().await,
+            }
+        })
+    }
+
+    /// Keeps the stored holidays up to date. Failing to fetch them isn't fatal, since the schedule
+    /// uses whichever holidays are already stored, so it is retried with a growing wait.
+    fn refresh_holidays(db: DatabaseManager, database: &str, source: HolidaySource, years: RangeInclusive<i32>) -> Subscription<Message> {
+        // Switching profile or holiday source starts again, as does a task due in a new year
+        let id = format!("{database} {source:?} {years:?}");
+        let start = HolidayRefresh {
+            db,
+            source,
+            years,
+            backoff: Backoff::new(iced::time::Duration::from_secs(60), HOLIDAY_CHECK_INTERVAL),
+            wait:    None,
+        };
+
+        iced::subscription::unfold(id, start, |mut refresh| async move {
+            if let Some(wait) = refresh.wait {
+                tokio::time::sleep(wait).await;
+            }
+
+            match refresh.db.try_update_holidays(&*refresh.source.provider(), refresh.years.clone()).await {
+                Ok(fetched) => {
+                    refresh.backoff.succeeded();
+                    refresh.wait = Some(HOLIDAY_CHECK_INTERVAL);
+                    (Message::HolidaysRefreshed(Ok(fetched)), refresh)
+                },
+                Err(e) => {
+                    let retry = refresh.backoff.failed();
+                    let message = Self::holidays_not_fetched(&refresh, &e, retry).await;
+                    refresh.wait = Some(retry);
+                    (Message::HolidaysRefreshed(Err(message)), refresh)
+                },
+            }
+        })
+    }
+
+    /// Explains that holidays could not be fetched, and how old the ones being used instead are
+    async fn holidays_not_fetched(refresh: &HolidayRefresh, error: &BeavorError, retry: iced::time::Duration) -> String {
+        let mut oldest: Option<FetchedHolidays> = None;
+        let mut missing = Vec::new();
+        for year in refresh.years.clone() {
+            match refresh.db.holidays_fetched(year).await {
+                Ok(Some(fetched)) => if oldest.as_ref().is_none_or(|o| fetched.fetched_at < o.fetched_at) {
+                    oldest = Some(fetched);
+                },
+                Ok(None) => missing.push(year.to_string()),
+                Err(e) => missing.push(format!("{year} ({e})")),
+            }
+        }
+
+        let using = match (missing.is_empty(), oldest) {
+            (false, _) => format!("the schedule has no holidays for {}", missing.join(", ")),
+            (true, Some(oldest)) => format!("using holidays from {} fetched {}", oldest.source, oldest.fetched_at.format("%F")),
+            (true, None) => "using the holidays already stored".to_string(),
+        };
+
+        format!(
+            "Could not fetch holidays from {}: {error}. Retrying in {} min, {using}",
+            refresh.source.provider().source(),
+            retry.as_secs() / 60
+        )
+    }
+
     fn update_loading(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::Loaded(state) => {
+                let refresh = Self::refresh(state.db.clone(), state.profile.flags.clone());
+                let backup = Self::backup_if_due(state.db.clone(), state.profile.backup_policy());
                 *self = Self::Loaded(state);
+                return Command::batch([refresh, backup]);
             },
-            Message::Tick(_) | Message::None => (),
-            m => panic!("Should never happen: {m:#?}")
+            Message::OpenFailed(failure) => *self = Self::Restoring(failure),
+            // Anything else is left over from the profile that was open before, or is a shortcut
+            // pressed before there is anything to act on
+            _ => (),
         }
         Command::none()
     }
 
+    /// Reloads the cache from the database
+    fn refresh(db: DatabaseManager, flags: Flags) -> Command<Message> {
+        Command::perform(Cache::load(db, flags), |r| Self::or_error(r.map(Message::Refresh)))
+    }
+
+    /// Loads a page of finished tasks into the archive
+    fn load_archive(db: DatabaseManager, filter: ArchiveFilter) -> Command<Message> {
+        Command::perform(async move {
+            db.finished_tasks(&filter).await
+        }, |r| Self::or_error(r.map(|tasks| Message::Archive(ArchiveMessage::Loaded(tasks)))))
+    }
+
+    /// Loads the tasks matching a search into the scroller
+    fn search(db: DatabaseManager, query: String) -> Command<Message> {
+        Command::perform(async move {
+            let results = db.search(&query).await;
+            results.map(|tasks| (query, tasks))
+        }, |r| Self::or_error(r.map(|(query, tasks)| Message::Search(SearchMessage::Loaded(query, tasks)))))
+    }
+
+    /// Loads the tasks in the trash
+    fn load_trash(db: DatabaseManager) -> Command<Message> {
+        Command::perform(async move {
+            db.trashed_tasks().await
+        }, |r| Self::or_error(r.map(Message::TrashLoaded)))
+    }
+
+    /// Loads the change history of a task into the editor
+    fn load_history(db: DatabaseManager, id: u32) -> Command<Message> {
+        Command::perform(async move {
+            db.task_history(id).await
+        }, |r| Self::or_error(r.map(Message::HistoryLoaded)))
+    }
+
+    /// Routes a failed backend call to the error bar
+    fn or_error(result: Result<Message, BeavorError>) -> Message {
+        result.unwrap_or_else(|e| Message::Error(Some(e.to_string())))
+    }
+
     fn update_loaded(&mut self, message: Message) -> Command<Message> {
         let state = match self {
             Beavor::Loaded(state) => state,
-            Beavor::Loading => panic!("Should never happen"),
+            Beavor::ChoosingProfile { .. } | Beavor::Restoring(_) | Beavor::Loading => panic!("Should never happen"),
         };
 
         match message {
-            Message::Mutate(mutate_message) => Beavor::mutate(&state.db, &mut state.displayed_task, &mutate_message, &state.flags.work_week, state.timesheet.clone()),
+            Message::Mutate(mutate_message) => Beavor::mutate(&state.db, &mut state.displayed_task, &mutate_message, &state.profile.flags, state.timesheet.clone(), &state.profiles),
             other => {match other {
                 Message::Modal(modal_message) => {
                     match modal_message {
@@ -318,6 +811,101 @@ impl Beavor {
                         Command::none()
                     }
                 },
+                Message::Archive(archive_message) => match state.archive_state.update(archive_message) {
+                    Some(filter) => Self::load_archive(state.db.clone(), filter),
+                    None => Command::none(),
+                },
+                Message::Search(search_message) => match state.search.update(search_message) {
+                    Some(query) => Self::search(state.db.clone(), query),
+                    None => Command::none(),
+                },
+                Message::Refresh(cache) => {
+                    state.cache = cache;
+                    // This is called after mutating state, e.g., saving a task
+                    // If the task was finished, need to also clear the displayed task
+                    if state.displayed_task.draft.finished {
+                        state.displayed_task.select(None);
+                    }
+
+                    // Show changes saved elsewhere to the displayed task, unless it has unsaved changes
+                    if state.displayed_task.is_unmodified() {
+                        let draft = &state.displayed_task.draft;
+                        if let Some(saved) = state.cache.loaded_tasks.iter().find(|t| draft.id.is_some() && t.id == draft.id && t.revision != draft.revision) {
+                            state.displayed_task.select(Some(saved.clone()));
+                        }
+                    }
+
+                    // The change may also affect what a search matches
+                    match state.search.reload() {
+                        Some(query) => Self::search(state.db.clone(), query),
+                        None => Command::none(),
+                    }
+                },
+                Message::ShowPane(pane) => {
+                    state.pane = pane;
+                    match pane {
+                        Pane::Open => Command::none(),
+                        Pane::Archive => self.update(Message::Archive(ArchiveMessage::Reload)),
+                        Pane::Trash => Self::load_trash(state.db.clone()),
+                    }
+                },
+                Message::Profile(ProfileMessage::TrySwitch) => {
+                    // Don't lose a modified task
+                    if state.displayed_task.is_unmodified() {
+                        self.update(Message::Profile(ProfileMessage::ForceSwitch))
+                    } else {
+                        Self::update_modal_state(&mut state.modal_state, ModalType::Confirm(ConfirmationRequest {
+                            message: "Unsaved changes will be lost. Switch profile without saving?".to_string(),
+                            run_on_confirm: Box::new(Message::Profile(ProfileMessage::ForceSwitch))
+                        }));
+                        Command::none()
+                    }
+                },
+                Message::Profile(ProfileMessage::ForceSwitch) => {
+                    let profiles = state.profiles.clone();
+                    *self = Beavor::ChoosingProfile { profiles, new_name: String::new() };
+                    Command::none()
+                },
+                Message::ResolveConflict(resolution) => {
+                    let ModalType::Conflict(theirs) = std::mem::replace(&mut state.modal_state, ModalType::None) else {
+                        panic!("Should never happen")
+                    };
+                    state.displayed_task.resolve_conflict(*theirs, resolution);
+
+                    match resolution {
+                        ConflictResolution::Overwrite => self.update(Message::Mutate(MutateMessage::SaveDraftTask)),
+                        ConflictResolution::Merge | ConflictResolution::Discard => Command::none(),
+                    }
+                },
+                Message::BackupIfDue => Self::backup_if_due(state.db.clone(), state.profile.backup_policy()),
+                Message::DatabaseChanged => {
+                    let refresh = Self::refresh(state.db.clone(), state.profile.flags.clone());
+                    // Also reload whatever list is shown, since the change may affect it
+                    let pane = state.pane;
+                    let reload = match pane {
+                        Pane::Open => Command::none(),
+                        Pane::Archive => self.update(Message::Archive(ArchiveMessage::Reload)),
+                        Pane::Trash => Self::load_trash(state.db.clone()),
+                    };
+                    Command::batch([refresh, reload])
+                },
+                Message::HolidaysRefreshed(result) => {
+                    // Some years may have been stored before another failed
+                    let fetched = !matches!(result, Ok(false));
+                    state.error_bar.holidays = result.err();
+                    if fetched {
+                        Self::refresh(state.db.clone(), state.profile.flags.clone())
+                    } else {
+                        Command::none()
+                    }
+                },
+                Message::ShowEditorTab(tab) => {
+                    state.displayed_task.tab = tab;
+                    match (tab, state.displayed_task.draft.id) {
+                        (EditorTab::History, Some(id)) => Self::load_history(state.db.clone(), id),
+                        _ => Command::none(),
+                    }
+                },
                 other => {
                     match other {
                         Message::TryNewTask => Self::try_select_task(state, None),
@@ -325,7 +913,7 @@ impl Beavor {
                             // Confirm before deleting
                             let name = state.displayed_task.draft.name.clone();
                             Self::update_modal_state(&mut state.modal_state, ModalType::Confirm(ConfirmationRequest {
-                                message: format!("Are you sure you want to delete ' {name}'?"),
+                                message: format!("Move ' {name}' to the trash?"),
                                 run_on_confirm: Box::new(Message::Mutate(MutateMessage::ForceDeleteTask))
                             }));
                         },
@@ -337,28 +925,21 @@ impl Beavor {
                         Message::TrySelectTask(maybe_task) => Self::try_select_task(state, maybe_task),
                         Message::ForceSelectTask(maybe_task) => state.displayed_task.select(maybe_task),
                         Message::Timer(message) => state.displayed_task.update_timer(message),
-                        Message::Refresh(cache) => {
-                            state.cache = cache;
-                            // This is called after mutating state, e.g., saving a task
-                            // If the task was finished, need to also clear the displayed task
-                            if state.displayed_task.draft.finished {
-                                state.displayed_task.select(None);
-                            }
-                        },
                         Message::SetEditingLinkID(h_id) => Self::set_editing_link_id(state, h_id),
                         Message::Calendar(calendar_message) => state.calendar_state.update(calendar_message),
                         Message::UpdateFlags(new_flags) => {
-                            fs::write(
-                                CONFIG_FILE_PATH, serde_json::to_string_pretty(&new_flags)
-                                  .expect("I don't know how this could fail")
-                            ).expect("Panics if cannot write to filesystem");
+                            state.profiles.set_flags(&state.profile.name, new_flags.clone());
+                            state.profiles.save().expect("Panics if cannot write to filesystem");
 
-                            state.flags = new_flags;
+                            state.profile.flags = new_flags;
                         },
                         Message::Error(maybe_error) => state.error_bar.error = maybe_error,
-                        Message::Tick(_) | Message::None => (),
+                        Message::HistoryLoaded(history) => state.displayed_task.history = history,
+                        Message::TrashLoaded(trashed) => state.trash = trashed,
+                        // Choosing and creating profiles only happens before one is loaded
+                        Message::Tick(_) | Message::None | Message::Profile(_) => (),
                         Message::Modal(_) => panic!("Can never happen"),
-                        Message::Loaded(_) | Message::Mutate(_) |  Message::Open(_)=> panic!("Should never happen"),
+                        Message::Loaded(_) | Message::ResolveConflict(_) | Message::OpenFailed(_) | Message::Restore(_) | Message::BackupIfDue | Message::DatabaseChanged | Message::HolidaysRefreshed(_) | Message::Mutate(_) |  Message::Open(_) | Message::Archive(_) | Message::ShowPane(_) | Message::ShowEditorTab(_) | Message::Search(_) | Message::Refresh(_) | Message::ForProfile(..) => panic!("Should never happen"),
                     }
                     Command::none()
                 }
@@ -402,14 +983,14 @@ impl Beavor {
         }
     }
 
-    fn mutate(db: &DatabaseManager, displayed_task: &mut DisplayedTask, message: &MutateMessage, work_week: &WorkWeek, timesheet: Arc<Mutex<TimeSheet>>) -> Command<Message> {
+    fn mutate(db: &DatabaseManager, displayed_task: &mut DisplayedTask, message: &MutateMessage, flags: &Flags, timesheet: Arc<Mutex<TimeSheet>>, profiles: &Profiles) -> Command<Message> {
         displayed_task.stop_timer();
         // TODO this is so stupid but it works and I got tired of hacking at Arc<>
         let db_clone1 = db.clone();
         let db_clone2 = db.clone();
         let t1 = displayed_task.clone();
         let t2 = displayed_task.draft.clone();
-        let work_week_clone = work_week.clone();
+        let flags = flags.clone();
 
         let (tx, rx) = oneshot::channel::<()>(); // Synchronize the writes to the database with the reads that update the cache
 
@@ -418,59 +999,126 @@ impl Beavor {
                 match message {
                     MutateMessage::SaveDraftTask => match t1.draft.id {
                         Some(_) => Command::perform(async move {
-                            // Log time worked to the timesheet
-                            timesheet
-                                .lock().
-                                expect("Fails if mutex is poisoned")
-                                .log_time(t1.added_time().expect("We've checked that the id is Some, so the task already exists and this will also be Some"), &t1.draft)
-                                .expect("Fails if cannot write to timesheet csv file");
-
                             // Update the database with the new task
-                            db_clone1.update_task(&t1.draft).await
-                                .expect("The task should already exist");
+                            let updated = db_clone1.update_task(&t1.draft).await;
 
                             // Done - let the cache refresh
                             tx.send(()).unwrap();
-                        }, |()| Message::ForceSelectTask(Some(t2))),
+
+                            match updated {
+                                // Log time worked to the timesheet, once it is saved
+                                Ok(saved) => timesheet
+                                    .lock().
+                                    expect("Fails if mutex is poisoned")
+                                    .log_time(t1.added_time().expect("We've checked that the id is Some, so the task already exists and this will also be Some"), &t1.draft)
+                                    .map(|()| Message::ForceSelectTask(Some(saved))),
+                                // Let the user choose what to do with their changes
+                                Err(BeavorError::Conflict(theirs)) => Ok(Message::Modal(ModalMessage::Show(ModalType::Conflict(theirs)))),
+                                Err(e) => Err(e),
+                            }
+                        }, Self::or_error),
                         None => Command::perform(async move {
-                            let t: Task = db_clone1.create_task(&t1.draft).await;
+                            let t = db_clone1.create_task(&t1.draft).await;
                             tx.send(()).unwrap();
-                            t
-                        }, |t: Task| Message::ForceSelectTask(Some(t))),
+                            t.map(|t| Message::ForceSelectTask(Some(t)))
+                        }, Self::or_error),
                     },
                     MutateMessage::ForceDeleteTask => {
                         let t = std::mem::take(&mut displayed_task.draft);
                         displayed_task.select(None);
                         Command::perform(async move {
-                            db_clone1.delete_task(t).await;
+                            let deleted = db_clone1.delete_task(t).await;
 
                             // Done - let the cache refresh
                             tx.send(()).unwrap();
-                        }, |()| Message::TryNewTask)
+                            deleted.map(|()| Message::TryNewTask)
+                        }, Self::or_error)
                     },
                     MutateMessage::VacationStatus(date, is_vacation) => {
                         let date = *date; // These copies make lifetimes happy
                         let is_vacation = *is_vacation;
                         Command::perform(async move {
-                            if is_vacation {
-                                db_clone1.add_vacation_day(&date).await;
+                            let updated = if is_vacation {
+                                db_clone1.add_vacation_day(&date).await
                             }else {
-                                db_clone1.delete_vacation_day(&date).await;
-                            }
+                                db_clone1.delete_vacation_day(&date).await
+                            };
+                            tx.send(()).unwrap();
+                            updated.map(|()| Message::None)
+                        }, Self::or_error)
+                    },
+                    MutateMessage::AddDaysOff(days) => {
+                        let days = days.clone();
+                        Command::perform(async move {
+                            let added = db_clone1.add_days_off(&days).await;
+                            tx.send(()).unwrap();
+                            added.map(|()| Message::None)
+                        }, Self::or_error)
+                    },
+                    MutateMessage::DeleteDaysOff(dates) => {
+                        let dates = dates.clone();
+                        Command::perform(async move {
+                            let deleted = db_clone1.delete_days_off(&dates).await;
+                            tx.send(()).unwrap();
+                            deleted.map(|()| Message::None)
+                        }, Self::or_error)
+                    },
+                    MutateMessage::ReopenTask(task) => {
+                        let mut task = task.clone();
+                        task.finished = false;
+                        Command::perform(async move {
+                            let reopened = db_clone1.update_task(&task).await;
+                            tx.send(()).unwrap();
+                            reopened.map(|_| Message::Archive(ArchiveMessage::Reload))
+                        }, Self::or_error)
+                    },
+                    MutateMessage::RestoreTask(id) => {
+                        let id = *id;
+                        Command::perform(async move {
+                            let restored = db_clone1.restore_task(id).await;
                             tx.send(()).unwrap();
-                        }, |()| Message::None)
+                            restored.map(|_| Message::ShowPane(Pane::Trash))
+                        }, Self::or_error)
+                    },
+                    MutateMessage::MoveTask(name) => {
+                        let destination = profiles.get(name).map(|p| p.database.clone());
+                        let name = name.clone();
+                        let t = std::mem::take(&mut displayed_task.draft);
+                        displayed_task.select(None);
+                        Command::perform(async move {
+                            let moved = match destination {
+                                // Never creates the destination, so a missing database fails the move
+                                Some(path) => match DatabaseManager::new(&path).await {
+                                    Ok(destination) => db_clone1.move_task(t, &destination).await.map(|_| ()),
+                                    Err(e) => Err(e),
+                                },
+                                None => Err(BeavorError::NotFound(format!("No profile named '{name}'"))),
+                            };
+                            tx.send(()).unwrap();
+                            moved.map(|()| Message::TryNewTask)
+                        }, Self::or_error)
+                    },
+                    MutateMessage::Undo | MutateMessage::Redo => {
+                        let undo = matches!(message, MutateMessage::Undo);
+                        Command::perform(async move {
+                            let edit = if undo {db_clone1.undo().await} else {db_clone1.redo().await};
+                            tx.send(()).unwrap();
+
+                            // Show the displayed task as it is after the edit
+                            edit.map(|edit| {
+                                let shown = t2.id.is_some() && edit.as_ref().and_then(Edit::task_id) == t2.id;
+                                match edit {
+                                    Some(Edit::Task { after, .. }) if shown => Message::TrySelectTask(after),
+                                    _ => Message::None,
+                                }
+                            })
+                        }, Self::or_error)
                     },
                 },
                 Command::perform(async move {
                     rx.await.unwrap();
-                    let tasks = db_clone2.open_tasks().await;
-
-                    Cache {
-                        loaded_schedule: db_clone2.schedule(work_week_clone, &tasks).await,
-                        categories: ComboBoxState::new(Self::unique_categories(&tasks)),
-                        loaded_tasks: tasks,
-                    }
-                }, Message::Refresh)
+                    Cache::load(db_clone2, flags).await
+                }, |r| Self::or_error(r.map(Message::Refresh)))
             ]
         )
     }
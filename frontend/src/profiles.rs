@@ -0,0 +1,164 @@
+use std::fs;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use chrono::Duration;
+
+use backend::BackupPolicy;
+
+use crate::Flags;
+
+const PROFILES_PATH: &str = "./resources/profiles.json";
+
+// Where everything was kept before there were profiles. These become the default profile
+const LEGACY_CONFIG_PATH:    &str = "./resources/config.json";
+const LEGACY_WORKLIST_PATH:  &str = "worklist.db";
+const LEGACY_TIMESHEET_PATH: &str = "timesheet.csv";
+const DEFAULT_PROFILE:       &str = "Default";
+
+/// A separate worklist, with its own database, timesheet and work week
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name:      String,
+    pub database:  String,
+    pub timesheet: String,
+    #[serde(default)]
+    pub flags:     Flags,
+}
+
+impl Profile {
+    pub fn backup_policy(&self) -> BackupPolicy {
+        BackupPolicy {
+            interval: Duration::hours(self.flags.backup_interval_hours.into()),
+            keep:     self.flags.backups_kept,
+            ..BackupPolicy::for_database(&self.database)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    /// Names of profiles, most recently used first
+    recent:   Vec<String>,
+}
+
+impl Profiles {
+    /// Impure (reads from the filesystem)
+    ///
+    /// Reads the saved profiles. If there are none, the worklist and settings from before there
+    /// were profiles become the only profile, so there is always at least one.
+    ///
+    /// # Panics
+    /// Panics if the profiles file is incorrectly formatted
+    pub fn load() -> Self {
+        let mut profiles: Profiles = fs::read_to_string(PROFILES_PATH)
+            .map(|json| serde_json::from_str(&json).expect("Panics if profiles file incorrectly formatted"))
+            .unwrap_or_default();
+
+        if profiles.profiles.is_empty() {
+            profiles.profiles.push(Profile {
+                name:      DEFAULT_PROFILE.into(),
+                database:  LEGACY_WORKLIST_PATH.into(),
+                timesheet: LEGACY_TIMESHEET_PATH.into(),
+                flags:     fs::read_to_string(LEGACY_CONFIG_PATH)
+                    .map(|json| serde_json::from_str(&json).expect("Panics if config file incorrectly formatted"))
+                    .unwrap_or_default(),
+            });
+        }
+
+        profiles
+    }
+
+    /// Impure (writes to the filesystem)
+    /// # Errors
+    /// Returns an error if the profiles file cannot be written
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(
+            PROFILES_PATH,
+            serde_json::to_string_pretty(self).expect("Profiles contain only strings and numbers, so always serialize")
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    /// Returns the profiles, most recently used first
+    pub fn all(&self) -> Vec<&Profile> {
+        let mut all: Vec<&Profile> = self.profiles.iter().collect();
+        all.sort_by_key(|p| self.recent.iter().position(|name| *name == p.name).unwrap_or(usize::MAX));
+        all
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    pub fn most_recent(&self) -> &Profile {
+        self.all()[0]
+    }
+
+    /// Moves a profile to the front of the recently used profiles
+    pub fn mark_used(&mut self, name: &str) {
+        self.recent.retain(|n| n != name);
+        self.recent.insert(0, name.to_string());
+    }
+
+    /// Returns why a new profile can't be named `name`, if it can't. Its database and timesheet
+    /// are named after it, so the name must be a file name, and those files can't be another
+    /// profile's.
+    pub fn check_new_name(&self, name: &str) -> Result<(), String> {
+        const NOT_IN_FILE_NAMES: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("A profile needs a name".into());
+        }
+        if name.starts_with('.') || name.contains("..") || name.chars().any(|c| c.is_control() || NOT_IN_FILE_NAMES.contains(&c)) {
+            return Err(format!("A profile's name can't start with '.', contain '..', or contain any of {}", NOT_IN_FILE_NAMES.iter().collect::<String>()));
+        }
+        if self.get(name).is_some() {
+            return Err(format!("There is already a profile named {name}"));
+        }
+
+        // File names may not be case sensitive
+        let (database, timesheet) = Self::files_for(name);
+        let taken = |file: &str| self.profiles.iter().any(|p| p.database.eq_ignore_ascii_case(file) || p.timesheet.eq_ignore_ascii_case(file));
+        if let Some(file) = [database, timesheet].into_iter().find(|file| taken(file)) {
+            return Err(format!("Another profile already uses {file}"));
+        }
+
+        Ok(())
+    }
+
+    /// The database and timesheet of a new profile named `name`
+    fn files_for(name: &str) -> (String, String) {
+        (format!("{name}.db"), format!("{name}_timesheet.csv"))
+    }
+
+    /// Adds a profile with a new database and timesheet named after it.
+    /// Returns `None` if `check_new_name` rejects the name.
+    pub fn create(&mut self, name: &str) -> Option<&Profile> {
+        let name = name.trim();
+        self.check_new_name(name).ok()?;
+
+        let (database, timesheet) = Self::files_for(name);
+        self.profiles.push(Profile {
+            name:      name.to_string(),
+            database,
+            timesheet,
+            flags:     Flags::default(),
+        });
+
+        self.profiles.last()
+    }
+
+    pub fn set_flags(&mut self, name: &str, flags: Flags) {
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == name) {
+            profile.flags = flags;
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use iced::widget::{
+    Column,
+    column,
+    row,
+    scrollable,
+    text,
+    text_input,
+    button,
+};
+
+use iced::{
+    Element,
+    Length,
+    Alignment,
+};
+
+use backend::{
+    Task,
+    ArchiveFilter,
+    utils::parse_date,
+};
+
+use crate::{
+    Message as MessageWrapper,
+    MutateMessage,
+};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Text(String),
+    Category(String),
+    From(String),
+    To(String),
+    Search,
+    Reload,
+    PreviousPage,
+    NextPage,
+    Loaded(Vec<Task>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    text:     String,
+    category: String,
+    from:     String,
+    to:       String,
+    page:     u32,
+    results:  Vec<Task>,
+}
+
+impl State {
+    /// Returns the filter to query with if the results need to be reloaded
+    pub fn update(&mut self, message: Message) -> Option<ArchiveFilter> {
+        match message {
+            Message::Text(text)         => self.text = text,
+            Message::Category(category) => self.category = category,
+            Message::From(from)         => self.from = from,
+            Message::To(to)             => self.to = to,
+            Message::Search => {
+                self.page = 0;
+                return Some(self.filter());
+            },
+            Message::Reload => return Some(self.filter()),
+            Message::PreviousPage => {
+                self.page = self.page.saturating_sub(1);
+                return Some(self.filter());
+            },
+            Message::NextPage => {
+                self.page += 1;
+                return Some(self.filter());
+            },
+            Message::Loaded(tasks) => self.results = tasks,
+        }
+        None
+    }
+
+    /// Dates that don't parse are ignored rather than matching nothing
+    fn filter(&self) -> ArchiveFilter {
+        ArchiveFilter {
+            from:     parse_date(&self.from).ok(),
+            to:       parse_date(&self.to).ok(),
+            category: Some(self.category.clone()).filter(|c| !c.is_empty()),
+            text:     Some(self.text.clone()).filter(|t| !t.is_empty()),
+            page:     self.page,
+            ..ArchiveFilter::default()
+        }
+    }
+
+    fn is_last_page(&self) -> bool {
+        self.results.len() < ArchiveFilter::default().page_size as usize
+    }
+}
+
+pub fn archive(state: &State) -> Column<'static, MessageWrapper> {
+    let search = MessageWrapper::Archive(Message::Search);
+
+    column![
+        text_input("Search...", &state.text)
+            .on_input(|s| MessageWrapper::Archive(Message::Text(s)))
+            .on_submit(search.clone()),
+        row![
+            text_input("Category...", &state.category)
+                .on_input(|s| MessageWrapper::Archive(Message::Category(s)))
+                .on_submit(search.clone()),
+            text_input("From (YYYY-MM-DD)...", &state.from)
+                .on_input(|s| MessageWrapper::Archive(Message::From(s)))
+                .on_submit(search.clone()),
+            text_input("To (YYYY-MM-DD)...", &state.to)
+                .on_input(|s| MessageWrapper::Archive(Message::To(s)))
+                .on_submit(search.clone()),
+        ]
+            .spacing(4),
+        button("Search").on_press(search),
+        scrollable(
+            Column::with_children(
+                state.results
+                    .iter()
+                    .map(archived_task_row)
+                    .collect()
+            )
+                .width(Length::Shrink)
+                .spacing(2)
+        )
+            .height(Length::Fill),
+        row![
+            button("<").on_press_maybe((state.page > 0).then_some(MessageWrapper::Archive(Message::PreviousPage))),
+            text(format!("Page {}", state.page + 1)),
+            button(">").on_press_maybe((!state.is_last_page()).then_some(MessageWrapper::Archive(Message::NextPage))),
+        ]
+            .align_items(Alignment::Center)
+            .spacing(4),
+    ]
+        .spacing(4)
+        .padding(4)
+}
+
+fn archived_task_row(task: &Task) -> Element<'static, MessageWrapper> {
+    row![
+        button(
+            column![
+                text(&task.name),
+                text(&task.category),
+                text(task.finished_date.map_or_else(|| "Finished: unknown".to_string(), |d| format!("Finished: {d}"))),
+            ]
+        )
+            .on_press(MessageWrapper::TrySelectTask(Some(task.clone())))
+            .width(Length::Fill),
+        button("Reopen")
+            .on_press(MessageWrapper::Mutate(MutateMessage::ReopenTask(task.clone()))),
+    ]
+        .align_items(Alignment::Center)
+        .spacing(4)
+        .into()
+}
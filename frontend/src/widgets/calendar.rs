@@ -5,6 +5,8 @@ use iced::widget::{
     column,
     button,
     text,
+    text_input,
+    pick_list,
     MouseArea,
 };
 
@@ -12,19 +14,32 @@ use iced::{
     Element,
     Length,
     Alignment,
+    theme,
 };
 
+use std::collections::HashMap;
+
 use chrono::{
     Weekday,
     NaiveDate,
+    NaiveTime,
     naive::Days,
     Duration,
     Datelike,
 };
 
 use backend::{
-    utils::today_date,
+    utils::{
+        today_date,
+        parse_date,
+    },
     Schedule,
+    DayOff,
+    days_off::Reason,
+    schedule::{
+        HourRange,
+        Strategy,
+    },
 };
 
 use iced_aw::{
@@ -37,14 +52,69 @@ use iced_aw::{
 
 use crate::Message as MessageWrapper;
 use crate::MutateMessage;
+use crate::widgets::AT_RISK;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
     ScrollDown,
     ScrollUp,
     ScrollUpMax,
     FilterToDate(Option<NaiveDate>), //TODO I have a feeling I'll want more filters at some point
     ClickDate(Option<NaiveDate>),
+    DaysOffTo(String),
+    DaysOffReason(Reason),
+    DaysOffLabel(String),
+    DaysOffStart(String),
+    DaysOffEnd(String),
+    CompareWith(Option<Strategy>),
+}
+
+/// The reasons a day can be booked off. Holidays are fetched, not booked.
+static DAY_OFF_REASONS: [Reason; 2] = [Reason::Vacation, Reason::Travel];
+
+/// The strategies the schedule can be compared with
+static STRATEGIES: [Strategy; 3] = Strategy::ALL;
+
+/// Booking days off, from the selected date to `to`
+#[derive(Debug, Clone)]
+pub struct DaysOffForm {
+    to:     String,
+    reason: Reason,
+    label:  String,
+    /// The hours off, for part of a day. Both are blank for whole days
+    start:  String,
+    end:    String,
+}
+
+impl Default for DaysOffForm {
+    fn default() -> Self {
+        Self {
+            to:     String::new(),
+            reason: Reason::Vacation,
+            label:  String::new(),
+            start:  String::new(),
+            end:    String::new(),
+        }
+    }
+}
+
+impl DaysOffForm {
+    /// Returns the days off entered, from `from` to the date in `to`, or only `from` if `to` is
+    /// blank. `None` if a date or time doesn't parse, or the hours off end before they start.
+    fn days_off(&self, from: NaiveDate) -> Option<Vec<DayOff>> {
+        let parse_time = |time: &str| NaiveTime::parse_from_str(time, "%H:%M").ok();
+
+        let to = match self.to.trim() {
+            "" => from,
+            to => parse_date(to).ok()?,
+        };
+        let hours = match (self.start.trim(), self.end.trim()) {
+            ("", "") => None,
+            (start, end) => Some(HourRange::new(parse_time(start)?, parse_time(end)?)?),
+        };
+
+        Some(DayOff::range(from, to, self.reason, self.label.trim(), hours))
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -52,6 +122,9 @@ pub struct State {
     weeks_scrolled: u16,
     pub clicked_date: Option<NaiveDate>,
     pub filter_date: Option<NaiveDate>,
+    days_off: DaysOffForm,
+    /// The strategy whose schedule is shown beside the loaded one, if any
+    compare_with: Option<Strategy>,
 }
 
 impl State {
@@ -74,11 +147,68 @@ impl State {
             Message::ScrollUpMax        => self.scroll_up_max(),
             Message::FilterToDate(date) => self.filter_date = date,
             Message::ClickDate(d)       => self.clicked_date = d,
+            Message::DaysOffTo(to)      => self.days_off.to = to,
+            Message::DaysOffReason(r)   => self.days_off.reason = r,
+            Message::DaysOffLabel(l)    => self.days_off.label = l,
+            Message::DaysOffStart(t)    => self.days_off.start = t,
+            Message::DaysOffEnd(t)      => self.days_off.end = t,
+            Message::CompareWith(s)     => self.compare_with = s,
         }
     }
 }
 
-pub fn calendar(schedule: &Schedule, state: &State) -> Element<'static, MessageWrapper> {
+pub fn calendar(schedule: &Schedule, compared_schedules: &HashMap<Strategy, Schedule>, state: &State) -> Element<'static, MessageWrapper> {
+    let compared = state.compare_with.and_then(|strategy| Some((strategy, compared_schedules.get(&strategy)?)));
+
+    let schedules = match compared {
+        Some((strategy, compared)) => row![
+            column![text("Loaded"), weeks(schedule, state)].align_items(Alignment::Center),
+            column![text(strategy), weeks(compared, state)].align_items(Alignment::Center),
+        ],
+        None => row![weeks(schedule, state)],
+    };
+
+    let weeks = row![
+        schedules,
+            column![
+                button(text(icon_to_char(Icon::ChevronDoubleUp)).font(ICON_FONT))
+                    .on_press_maybe(if state.weeks_scrolled > 0 {Some(MessageWrapper::Calendar(Message::ScrollUpMax))}else {None}),
+                button(text(icon_to_char(Icon::ChevronUp)).font(ICON_FONT))
+                    .on_press_maybe(if state.weeks_scrolled > 0 {Some(MessageWrapper::Calendar(Message::ScrollUp))}else {None}),
+                button(text(icon_to_char(Icon::ChevronDown)).font(ICON_FONT))
+                    .on_press(MessageWrapper::Calendar(Message::ScrollDown)),
+            ].height(Length::Shrink)
+    ]
+        .align_items(Alignment::Center);
+
+    let feasibility = schedule.feasibility();
+    let summary = (!feasibility.is_feasible()).then(|| format!(
+        "{} tasks over capacity, {} days overbooked, {} tasks with no days to work on",
+        feasibility.at_risk.len(),
+        feasibility.overbooked.len(),
+        feasibility.unschedulable.len(),
+    ));
+
+    column![
+        weeks,
+        text(summary.unwrap_or_default()).style(theme::Text::Color(AT_RISK)),
+        row![
+            text("Compare with"),
+            pick_list(&STRATEGIES[..], state.compare_with, |s| MessageWrapper::Calendar(Message::CompareWith(Some(s)))),
+            button("Hide").on_press_maybe(
+                state.compare_with.map(|_| MessageWrapper::Calendar(Message::CompareWith(None)))
+            ),
+        ]
+            .spacing(4)
+            .align_items(Alignment::Center),
+        days_off_form(state),
+    ]
+        .align_items(Alignment::Center)
+        .into()
+}
+
+/// The weeks shown of one schedule, with the time assigned to each day
+fn weeks(schedule: &Schedule, state: &State) -> Row<'static, MessageWrapper> {
     // Get the days of the week that contains the passed day
     fn week_of(d: NaiveDate) -> Vec<NaiveDate> {
         let w = d.week(Weekday::Mon);
@@ -93,48 +223,82 @@ pub fn calendar(schedule: &Schedule, state: &State) -> Element<'static, MessageW
     let today = today_date();
 
     let num_weeks = 4;
-    row![
-        Row::with_children(
-            week_of(today + Days::new((7*state.weeks_scrolled).into()))
-                .iter()
-                .map(|d| column![
-                     text(d.weekday()),
-                     // TODO would be nice to throw a Rule::horizontal(4) in here, but it wants to
-                     // be wide and I haven't taken the time to figure out how to fix that
-                     Column::with_children(
-                        (0..num_weeks)
-                            .map(|n| *d + Days::new(7*n))
-                            .map(|d| Element::from(cal_day(
-                                d,
-                                schedule.get_time_assigned_on_day(d),
-                                Some(d) == state.filter_date,
-                                state.clicked_date.as_ref(),
-                                state.filter_date.as_ref(),
-                            )))
-                            .collect()
-                        )
-                    ]
-                        .align_items(Alignment::Center)
-                        .into()
-                ).collect()
-        )
-            .width(Length::Shrink)
-            .height(Length::Shrink)
-            .padding(8),
-            column![
-                button(text(icon_to_char(Icon::ChevronDoubleUp)).font(ICON_FONT))
-                    .on_press_maybe(if state.weeks_scrolled > 0 {Some(MessageWrapper::Calendar(Message::ScrollUpMax))}else {None}),
-                button(text(icon_to_char(Icon::ChevronUp)).font(ICON_FONT))
-                    .on_press_maybe(if state.weeks_scrolled > 0 {Some(MessageWrapper::Calendar(Message::ScrollUp))}else {None}),
-                button(text(icon_to_char(Icon::ChevronDown)).font(ICON_FONT))
-                    .on_press(MessageWrapper::Calendar(Message::ScrollDown)),
-            ].height(Length::Shrink)
+    Row::with_children(
+        week_of(today + Days::new((7*state.weeks_scrolled).into()))
+            .iter()
+            .map(|d| column![
+                 text(d.weekday()),
+                 // TODO would be nice to throw a Rule::horizontal(4) in here, but it wants to
+                 // be wide and I haven't taken the time to figure out how to fix that
+                 Column::with_children(
+                    (0..num_weeks)
+                        .map(|n| *d + Days::new(7*n))
+                        .map(|d| Element::from(cal_day(
+                            d,
+                            schedule.get_time_assigned_on_day(d),
+                            schedule.feasibility().time_overbooked(d),
+                            schedule.day_off(d),
+                            Some(d) == state.filter_date,
+                            state.clicked_date.as_ref(),
+                            state.filter_date.as_ref(),
+                        )))
+                        .collect()
+                    )
+                ]
+                    .align_items(Alignment::Center)
+                    .into()
+            ).collect()
+    )
+        .width(Length::Shrink)
+        .height(Length::Shrink)
+        .padding(8)
+}
+
+/// Books or clears days off from the selected date, e.g., a two-week vacation or an afternoon
+fn days_off_form(state: &State) -> Column<'static, MessageWrapper> {
+    let form = &state.days_off;
+    let on_input = |message: fn(String) -> Message| move |s: String| MessageWrapper::Calendar(message(s));
+    let days = state.filter_date.and_then(|from| form.days_off(from));
+
+    column![
+        text(match state.filter_date {
+            Some(from) => format!("Days off from {}", from.format("%b %d")),
+            None => "Select a date to book days off from".to_string(),
+        }),
+        row![
+            text_input("To (YYYY-MM-DD)...", &form.to).on_input(on_input(Message::DaysOffTo)),
+            pick_list(&DAY_OFF_REASONS[..], Some(form.reason), |r| MessageWrapper::Calendar(Message::DaysOffReason(r))),
+        ]
+            .spacing(4),
+        text_input("Label...", &form.label).on_input(on_input(Message::DaysOffLabel)),
+        row![
+            text_input("Off from (HH:MM)...", &form.start).on_input(on_input(Message::DaysOffStart)),
+            text_input("Off until (HH:MM)...", &form.end).on_input(on_input(Message::DaysOffEnd)),
+        ]
+            .spacing(4),
+        row![
+            button("Book").on_press_maybe(
+                days.clone().map(|days| MessageWrapper::Mutate(MutateMessage::AddDaysOff(days)))
+            ),
+            button("Clear").on_press_maybe(
+                days.map(|days| MessageWrapper::Mutate(MutateMessage::DeleteDaysOff(days.into_iter().map(|d| d.date).collect())))
+            ),
+        ]
+            .spacing(4),
     ]
-        .align_items(Alignment::Center)
-        .into()
+        .spacing(4)
+        .padding(8)
+        .width(Length::Fixed(320.0))
 }
 
-fn cal_day(day: NaiveDate, load: Option<Duration>, is_selected: bool, clicked_date: Option<&NaiveDate>, filter_date: Option<&NaiveDate>) -> Element<'static, MessageWrapper> {
+fn cal_day(day: NaiveDate, load: Option<Duration>, overbooked: Option<Duration>, day_off: Option<&DayOff>, is_selected: bool, clicked_date: Option<&NaiveDate>, filter_date: Option<&NaiveDate>) -> Element<'static, MessageWrapper> {
+    let right_press = match (day_off.map(|d| d.reason), load) {
+        // Holidays can't be cleared, and there's nothing to book on a day without work
+        (Some(Reason::StatHoliday), _) | (None, None) => MessageWrapper::None,
+        (Some(_), _) => MessageWrapper::Mutate(MutateMessage::DeleteDaysOff(vec![day])),
+        (None, Some(_)) => MessageWrapper::Mutate(MutateMessage::VacationStatus(day, true)),
+    };
+
     MouseArea::new(
         column![
             text(
@@ -148,8 +312,14 @@ fn cal_day(day: NaiveDate, load: Option<Duration>, is_selected: bool, clicked_da
                 // Casting to 64 reduces precision from 64 to 52 bits.
                 // This is ok because the number of minutes to work on a day will never occupy 52 bits
                 #[allow(clippy::cast_precision_loss)]
-                if let Some(load) = load {format!(" {:.1}", load.num_minutes() as f64/60.0)} else {"-".to_string()}
+                match (load, overbooked) {
+                    (Some(load), Some(over)) => format!(" {:.1} (+{:.1})", load.num_minutes() as f64/60.0, over.num_minutes() as f64/60.0),
+                    (Some(load), None) => format!(" {:.1}", load.num_minutes() as f64/60.0),
+                    (None, _) => "-".to_string(),
+                }
             )
+                .style(if overbooked.is_some() { theme::Text::Color(AT_RISK) } else { theme::Text::Default }),
+            text(day_off.map(describe_day_off).unwrap_or_default()).size(12),
         ]
             .padding(4)
             .align_items(Alignment::Center)
@@ -167,8 +337,16 @@ fn cal_day(day: NaiveDate, load: Option<Duration>, is_selected: bool, clicked_da
                 MessageWrapper::Calendar(Message::ClickDate(None))
             }
         )
-        .on_right_press(
-             MessageWrapper::Mutate(MutateMessage::VacationStatus(day, load.is_some()))
-        )
+        .on_right_press(right_press)
         .into()
 }
+
+/// The label of a day off, or why it's off if it has none, and which hours for part of a day
+fn describe_day_off(day_off: &DayOff) -> String {
+    let name = if day_off.label.is_empty() { day_off.reason.to_string() } else { day_off.label.clone() };
+
+    match day_off.hours {
+        Some(hours) => format!("{name} {}-{}", hours.start().format("%H:%M"), hours.end().format("%H:%M")),
+        None => name,
+    }
+}
@@ -19,6 +19,7 @@ use crate::{
     Message,
     ModalMessage,
     ModalType,
+    widgets::task_editor::ConflictResolution,
 };
 
 pub fn confirm_modal<'a>(state: &ModalType) -> Element<'a, Message> {
@@ -51,6 +52,29 @@ pub fn confirm_modal<'a>(state: &ModalType) -> Element<'a, Message> {
                     .on_close(Message::Modal(ModalMessage::Show(ModalType::None))),
             )
         },
+        ModalType::Conflict(theirs) => {
+            let resolve = |label: &'static str, resolution: ConflictResolution| button(text(label))
+                .width(Length::Fill)
+                .on_press(Message::ResolveConflict(resolution));
+
+            Some(
+                Card::new(
+                    text("Conflict:"),
+                    text(format!("'{}' was saved elsewhere while you were editing it. Keep your changes?", theirs.name)),
+                )
+                .foot(
+                    Row::new()
+                    .spacing(10)
+                    .padding(5)
+                    .width(Length::Fill)
+                    .push(resolve("Merge", ConflictResolution::Merge))
+                    .push(resolve("Overwrite", ConflictResolution::Overwrite))
+                    .push(resolve("Discard", ConflictResolution::Discard)),
+                )
+                    .max_width(400.0)
+                    .on_close(Message::Modal(ModalMessage::Show(ModalType::None))),
+            )
+        },
         _ => None,
     };
 
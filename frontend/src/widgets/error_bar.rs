@@ -8,9 +8,17 @@ use crate::Message;
 
 #[derive(Debug, Clone, Default)]
 pub struct State {
-    pub error: Option<String>,
+    pub error:    Option<String>,
+    /// Why holidays could not be fetched. Kept apart from `error`, since it is cleared once they
+    /// are fetched rather than by the next error
+    pub holidays: Option<String>,
 }
 
 pub fn error_bar(state: &State) -> Column<'_, Message> {
-    column![ text(state.error.clone().unwrap_or_default()) ]
+    let bar = column![ text(state.error.clone().unwrap_or_default()) ];
+
+    match &state.holidays {
+        Some(holidays) => bar.push(text(holidays)),
+        None => bar,
+    }
 }
@@ -1,7 +1,7 @@
 pub mod calendar;
 pub use calendar::calendar;
 
-mod task_scroller;
+pub mod task_scroller;
 pub use task_scroller::task_scroller;
 
 pub mod task_editor;
@@ -15,3 +15,18 @@ pub use confirm_modal::confirm_modal;
 
 pub mod error_bar;
 pub use error_bar::error_bar;
+
+pub mod archive;
+pub use archive::archive;
+
+pub mod trash;
+pub use trash::trash;
+
+pub mod profile_picker;
+pub use profile_picker::profile_picker;
+
+pub mod restore_dialog;
+pub use restore_dialog::restore_dialog;
+
+/// The colour of work that doesn't fit in the working hours before it is due
+pub const AT_RISK: iced::Color = iced::Color { r: 0.8, g: 0.2, b: 0.2, a: 1.0 };
@@ -0,0 +1,56 @@
+use iced::widget::{
+    Column,
+    column,
+    row,
+    text,
+    text_input,
+    button,
+};
+
+use iced::Alignment;
+
+use crate::{
+    Message,
+    profiles::Profiles,
+};
+
+#[derive(Debug, Clone)]
+pub enum ProfileMessage {
+    Choose(String),
+    NewName(String),
+    Create,
+    /// Go back to choosing a profile, if there are no unsaved changes
+    TrySwitch,
+    ForceSwitch,
+}
+
+pub fn profile_picker(profiles: &Profiles, new_name: &str) -> Column<'static, Message> {
+    let create = Message::Profile(ProfileMessage::Create);
+    let checked = profiles.check_new_name(new_name);
+
+    column![
+        text("Choose a profile"),
+        Column::with_children(
+            profiles.all()
+                .into_iter()
+                .map(|p| button(text(&p.name))
+                    .on_press(Message::Profile(ProfileMessage::Choose(p.name.clone())))
+                    .into()
+                )
+                .collect()
+        )
+            .spacing(4),
+        row![
+            text_input("New profile...", new_name)
+                .on_input(|s| Message::Profile(ProfileMessage::NewName(s)))
+                .on_submit(create.clone()),
+            button("Create").on_press_maybe(checked.is_ok().then_some(create)),
+        ]
+            .spacing(4),
+        // Nothing to explain until a name is typed
+        text(if new_name.trim().is_empty() { String::new() } else { checked.err().unwrap_or_default() }),
+    ]
+        .align_items(Alignment::Center)
+        .spacing(8)
+        .padding(8)
+}
@@ -0,0 +1,63 @@
+use iced::widget::{
+    Column,
+    column,
+    row,
+    text,
+    button,
+};
+
+use iced::Alignment;
+
+use backend::Backup;
+
+use crate::{
+    Message,
+    profiles::{
+        Profile,
+        Profiles,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub enum RestoreMessage {
+    Restore,
+    StartEmpty,
+    ChooseProfile,
+}
+
+/// Why the database of a profile could not be opened, and what it could be restored from
+#[derive(Debug, Clone)]
+pub struct OpenFailure {
+    pub profiles:      Profiles,
+    pub profile:       Profile,
+    pub error:         String,
+    /// The newest backup of the database that isn't damaged, if there are any. Damaged backups
+    /// are never offered, so a failed restore can't offer the same backup again.
+    pub latest_backup: Option<Backup>,
+    /// The database file doesn't exist, so starting again with an empty database loses nothing
+    pub missing:       bool,
+}
+
+pub fn restore_dialog(failure: &OpenFailure) -> Column<'static, Message> {
+    column![
+        text(format!("Could not open {}", failure.profile.database)),
+        text(&failure.error),
+        text(match &failure.latest_backup {
+            Some(backup) => format!("The latest good backup was taken {}", backup.taken_at.format("%F %H:%M")),
+            None => "There are no undamaged backups to restore".to_string(),
+        }),
+        row![
+            button("Restore backup").on_press_maybe(
+                failure.latest_backup.is_some().then_some(Message::Restore(RestoreMessage::Restore))
+            ),
+            button("Start empty").on_press_maybe(
+                failure.missing.then_some(Message::Restore(RestoreMessage::StartEmpty))
+            ),
+            button("Choose another profile").on_press(Message::Restore(RestoreMessage::ChooseProfile)),
+        ]
+            .spacing(4),
+    ]
+        .align_items(Alignment::Center)
+        .spacing(8)
+        .padding(8)
+}
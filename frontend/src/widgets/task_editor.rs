@@ -18,6 +18,7 @@ use iced::widget::{
     container,
     Container,
     pick_list,
+    scrollable,
     ComboBox,
     combo_box::State as ComboBoxState,
 };
@@ -31,8 +32,12 @@ use iced_aw::helpers::date_picker;
 
 use backend::{
     Task,
+    TaskChange,
     DueDate,
-    utils::today_date,
+    utils::{
+        today_date,
+        format_datetime,
+    },
     Hyperlink,
 };
 
@@ -98,12 +103,33 @@ impl TimerState {
     }
 }
 
+/// What to do with the draft when the task it was opened from has been saved elsewhere since
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Keep the fields changed in the draft, and take every other field from the saved task
+    Merge,
+    /// Save the draft over the saved task
+    Overwrite,
+    /// Drop the draft and show the saved task
+    Discard,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EditorTab {
+    #[default]
+    Details,
+    History,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct DisplayedTask {
     selected:               Option<Task>,
     pub draft:              Task,
     pub editing_link_idx:   Option<usize>,
     pub timer:              TimerState,
+    pub tab:                EditorTab,
+    /// The history of the selected task. Only loaded while the history tab is shown
+    pub history:            Vec<TaskChange>,
 }
 
 impl DisplayedTask {
@@ -119,6 +145,10 @@ impl DisplayedTask {
     }
 
     pub fn select(&mut self, maybe_task: Option<Task>) {
+        // The history belongs to the previously selected task
+        self.tab = EditorTab::Details;
+        self.history.clear();
+
         self.selected = maybe_task.clone();
         self.draft = match maybe_task {
             Some(t) =>  t.clone(),
@@ -126,6 +156,22 @@ impl DisplayedTask {
         };
     }
 
+    /// Takes `theirs` into account in the draft, where `theirs` is the selected task as it was
+    /// saved elsewhere since it was selected
+    pub fn resolve_conflict(&mut self, theirs: Task, resolution: ConflictResolution) {
+        match resolution {
+            ConflictResolution::Merge => {
+                let base = self.selected.clone().unwrap_or_default();
+                self.draft = Task::merge(&base, &self.draft, &theirs);
+                // The merged time used includes theirs, so only the time added here is logged
+                self.selected = Some(theirs);
+            },
+            // Still compared against what was selected, so that the time added here is logged
+            ConflictResolution::Overwrite => self.draft.revision = theirs.revision,
+            ConflictResolution::Discard => self.select(Some(theirs)),
+        }
+    }
+
     pub fn stop_timer(&mut self) {
         if let Some(duration) = self.timer.stop() {
             self.draft.time_used = self.draft.time_used + duration;
@@ -205,9 +251,60 @@ pub enum UpdateDraftTask {
     Link            (LinkMessage),
 }
 
-#[allow(clippy::too_many_lines)]
 pub fn task_editor<'a, 'b>(displayed_task: &'a DisplayedTask, modal_state: &ModalType, combo_box_state: &'b ComboBoxState<String>) -> Column<'a, Message>
 where 'b: 'a
+{
+    column![
+        row![
+            button("Details").on_press_maybe(
+                (displayed_task.tab != EditorTab::Details).then_some(Message::ShowEditorTab(EditorTab::Details))
+            ),
+            button("History").on_press_maybe(
+                // Unsaved tasks have no history
+                (displayed_task.tab != EditorTab::History && displayed_task.draft.id.is_some()).then_some(Message::ShowEditorTab(EditorTab::History))
+            ),
+        ]
+            .spacing(4),
+        match displayed_task.tab {
+            EditorTab::Details => task_details(displayed_task, modal_state, combo_box_state),
+            EditorTab::History => task_history(&displayed_task.history),
+        }
+            .height(Length::Fill),
+    ]
+        .spacing(4)
+        .align_items(Alignment::Center)
+}
+
+fn task_history<'a>(history: &[TaskChange]) -> Column<'a, Message> {
+    column![
+        scrollable(
+            Column::with_children(
+                history
+                    .iter()
+                    .map(|change| row![
+                        text(format_datetime(change.changed_at)).width(Length::FillPortion(2)),
+                        text(&change.changed_by).width(Length::FillPortion(1)),
+                        text(format!("{} {}", change.action, change.field)).width(Length::FillPortion(2)),
+                        text(format!(
+                            "{} → {}",
+                            change.old_value.as_deref().unwrap_or("-"),
+                            change.new_value.as_deref().unwrap_or("-"),
+                        )).width(Length::FillPortion(4)),
+                    ]
+                        .spacing(4)
+                        .into()
+                    )
+                    .collect()
+            )
+                .spacing(2)
+        )
+            .height(Length::Fill),
+    ]
+}
+
+#[allow(clippy::too_many_lines)]
+fn task_details<'a, 'b>(displayed_task: &'a DisplayedTask, modal_state: &ModalType, combo_box_state: &'b ComboBoxState<String>) -> Column<'a, Message>
+where 'b: 'a
 {
 
     #[allow(clippy::cast_possible_truncation)]
@@ -7,6 +7,7 @@ use iced::widget::{
     column,
     scrollable,
     text,
+    text_input,
     button,
     row,
     rule::Rule,
@@ -22,11 +23,13 @@ use iced::{
 use backend::{
     Task,
     Schedule,
+    schedule::Feasibility,
 };
 
 use crate::{
     Message,
     CalendarMessage,
+    widgets::AT_RISK,
 };
 
 trait Filter: Display { // TODO rather than display, should really impl Into<Element<'static, Message>>
@@ -58,33 +61,105 @@ impl Display for DateFilter<'_, '_> {
     }
 }
 
-pub fn task_scroller(tasks: &[Task], filter_date: Option<&NaiveDate>, schedule: &Schedule) -> Column<'static, Message> {
+struct SearchFilter<'search> {
+    query: &'search str,
+    results: &'search [Task],
+}
+
+impl Filter for SearchFilter<'_> {
+    fn apply(&self, t: &Task) -> bool {
+        self.results.iter().any(|r| r.id == t.id)
+    }
+
+    fn cancel(&self) -> Message {
+        Message::Search(SearchMessage::Text(String::new()))
+    }
+}
+
+impl Display for SearchFilter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Matching: {}", self.query)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SearchMessage {
+    Text(String),
+    /// The query that was searched for, and the tasks that matched it
+    Loaded(String, Vec<Task>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchState {
+    query:   String,
+    results: Option<Vec<Task>>,
+}
 
-    let filters = [ // TODO this should probably be in the application-level state
-        filter_date.map(|date| DateFilter {
+impl SearchState {
+    /// Returns the query to search for if the results need to be reloaded
+    pub fn update(&mut self, message: SearchMessage) -> Option<String> {
+        match message {
+            SearchMessage::Text(query) => {
+                self.query = query;
+                if self.query.trim().is_empty() {
+                    self.results = None;
+                }
+                return self.reload();
+            },
+            SearchMessage::Loaded(query, tasks) => {
+                // Searches finish out of order while typing, so only keep the latest
+                if query == self.query {
+                    self.results = Some(tasks);
+                }
+            },
+        }
+        None
+    }
+
+    /// Returns the current query, if there is one to search for
+    pub fn reload(&self) -> Option<String> {
+        (!self.query.trim().is_empty()).then(|| self.query.clone())
+    }
+}
+
+pub fn task_scroller(tasks: &[Task], filter_date: Option<&NaiveDate>, schedule: &Schedule, search: &SearchState) -> Column<'static, Message> {
+    // Searches include finished tasks, so the results replace the open tasks rather than narrowing them
+    let tasks = search.results.as_deref().unwrap_or(tasks);
+
+    let filters: Vec<Box<dyn Filter + '_>> = [ // TODO this should probably be in the application-level state
+        filter_date.map(|date| Box::new(DateFilter {
             date,
             schedule
-        }),
-    ];
+        }) as Box<dyn Filter + '_>),
+        search.results.as_deref().map(|results| Box::new(SearchFilter {
+            query: &search.query,
+            results,
+        }) as Box<dyn Filter + '_>),
+    ]
+        .into_iter()
+        .flatten()
+        .collect();
 
     column![
+        text_input("Search...", &search.query)
+            .on_input(|s| Message::Search(SearchMessage::Text(s))),
         Column::with_children(
             filters
                 .iter()
-                .filter_map(|f| f.as_ref().map(|f|
+                .map(|f|
                     row![
                         button("X").on_press(f.cancel()),
                         text(f.to_string())
                     ]
                     .align_items(Alignment::Center)
                     .spacing(4)
-                    .into())
+                    .into()
                 )
                 .chain(
-                    [if filters.iter().any(Option::is_some) {
-                        Rule::horizontal(2).into()
-                    }else {
+                    [if filters.is_empty() {
                         Space::with_height(0).into()
+                    }else {
+                        Rule::horizontal(2).into()
                     }]
                 )
                 .collect()
@@ -93,16 +168,8 @@ pub fn task_scroller(tasks: &[Task], filter_date: Option<&NaiveDate>, schedule:
             Column::with_children(
                 tasks
                     .iter()
-                    .filter(|t|
-                        filters
-                            .iter()
-                            .map(|f| match f {
-                                None => true,
-                                Some(f) => f.apply(t)
-                            })
-                            .all(|b| b)
-                    )
-                    .map(task_row)
+                    .filter(|t| filters.iter().all(|f| f.apply(t)))
+                    .map(|t| task_row(t, schedule.feasibility()))
                     .collect()
             )
                 .width(Length::Shrink)
@@ -114,12 +181,26 @@ pub fn task_scroller(tasks: &[Task], filter_date: Option<&NaiveDate>, schedule:
         .padding(4)
 }
 
-fn task_row(task: &Task) -> Element<'static, Message> {
+fn task_row(task: &Task, feasibility: &Feasibility) -> Element<'static, Message> {
+    // Casting to 64 reduces precision from 64 to 52 bits.
+    // This is ok because the number of minutes over will never occupy 52 bits
+    #[allow(clippy::cast_precision_loss)]
+    let warning = if feasibility.is_unschedulable(task.id) {
+        Some("No days to work on this before it's due".to_string())
+    } else {
+        feasibility.time_over(task.id).map(|over| format!("{:.1}h over capacity", over.num_minutes() as f64/60.0))
+    };
+
+    let row = column![
+        text(&task.name),
+        text(&task.category),
+    ];
+
     button(
-        column![
-            text(&task.name),
-            text(&task.category),
-        ]
+        match warning {
+            Some(warning) => row.push(text(warning).style(AT_RISK)),
+            None => row,
+        }
     )
         .on_press(Message::TrySelectTask(Some(task.clone())))
         .width(Length::Fill)
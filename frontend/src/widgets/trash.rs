@@ -0,0 +1,56 @@
+use iced::widget::{
+    Column,
+    column,
+    row,
+    scrollable,
+    text,
+    button,
+};
+
+use iced::{
+    Element,
+    Length,
+    Alignment,
+};
+
+use backend::TrashedTask;
+
+use crate::{
+    Message,
+    MutateMessage,
+};
+
+pub fn trash(trashed: &[TrashedTask]) -> Column<'static, Message> {
+    column![
+        text(if trashed.is_empty() {"The trash is empty"} else {"Deleted tasks"}),
+        scrollable(
+            Column::with_children(
+                trashed
+                    .iter()
+                    .map(trashed_task_row)
+                    .collect()
+            )
+                .width(Length::Shrink)
+                .spacing(2)
+        )
+            .height(Length::Fill),
+    ]
+        .spacing(4)
+        .padding(4)
+}
+
+fn trashed_task_row(trashed: &TrashedTask) -> Element<'static, Message> {
+    row![
+        column![
+            text(&trashed.task.name),
+            text(&trashed.task.category),
+            text(format!("Deleted: {}", trashed.deleted_at.format("%F %H:%M"))),
+        ]
+            .width(Length::Fill),
+        button("Restore")
+            .on_press_maybe(trashed.task.id.map(|id| Message::Mutate(MutateMessage::RestoreTask(id)))),
+    ]
+        .align_items(Alignment::Center)
+        .spacing(4)
+        .into()
+}